@@ -0,0 +1,211 @@
+//! Inti hashing yang deterministik: preimage dan hash header blok, cek difficulty, helper hex,
+//! dan Merkle tree.
+//!
+//! Modul ini hanya bergantung pada `sha2` dan tidak memakai `chrono`, `rayon`, `indicatif`,
+//! maupun `ctrlc`: tidak ada jam, thread, atau I/O di sini. Timestamp selalu diberikan pemanggil,
+//! sehingga logika ini bisa dipakai ulang di lingkungan terbatas tanpa membawa CLI-nya.
+
+use sha2::{Digest, Sha256};
+
+/// Jumlah bit pada hash SHA-256.
+pub const MAX_DIFFICULTY: u32 = 256;
+
+/// Menyusun preimage hash dari field header blok.
+pub fn block_hash_preimage(index: u64, timestamp: u64, previous_hash: &str, merkle_root: &str, nonce: u64, difficulty: u32) -> String {
+    format!("{}{}{}{}{}{}", index, timestamp, previous_hash, merkle_root, nonce, difficulty)
+}
+
+/// Menyambung field dengan awalan panjang (`<len>:<field>`) sehingga batas antar field tidak
+/// ambigu: tanpa awalan, amount 12 dengan fee 3 dan amount 1 dengan fee 23 menjadi string yang sama.
+pub fn length_prefixed(fields: &[&str]) -> String {
+    fields.iter().map(|field| format!("{}:{}", field.len(), field)).collect()
+}
+
+/// Menghitung hash SHA-256 dalam bentuk hex.
+pub fn sha256_hex(data: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(data.as_bytes());
+    format!("{:x}", hasher.finalize())
+}
+
+/// Mengecek bahwa `bits` bit teratas dari hash bernilai nol.
+pub fn meets_difficulty(hash_bytes: &[u8; 32], bits: u32) -> bool {
+    if bits > MAX_DIFFICULTY {
+        return false;
+    }
+    let full_bytes = (bits / 8) as usize;
+    let remaining_bits = bits % 8;
+    if hash_bytes[..full_bytes].iter().any(|&byte| byte != 0) {
+        return false;
+    }
+    remaining_bits == 0 || hash_bytes[full_bytes] >> (8 - remaining_bits) == 0
+}
+
+/// Target numerik untuk `bits`: hash (big-endian) memenuhi `meets_difficulty` tepat jika `<=` target.
+/// Hasilnya bisa dipakai langsung sebagai target `mine_block_to_target` lewat hex-nya.
+/// `bits` di atas `MAX_DIFFICULTY` diperlakukan sama dengan `MAX_DIFFICULTY`.
+pub fn difficulty_target(bits: u32) -> [u8; 32] {
+    let bits = bits.min(MAX_DIFFICULTY);
+    let mut target = [0xffu8; 32];
+    let full_bytes = (bits / 8) as usize;
+    target[..full_bytes].fill(0);
+    if full_bytes < target.len() {
+        target[full_bytes] = 0xff >> (bits % 8);
+    }
+    target
+}
+
+/// Mengubah byte menjadi hex huruf kecil.
+pub fn encode_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Mendekode string hex menjadi byte; `None` jika panjangnya ganjil atau ada karakter non-hex.
+pub fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) || !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+    (0..hex.len()).step_by(2).map(|i| u8::from_str_radix(&hex[i..i + 2], 16).ok()).collect()
+}
+
+/// Mendekode hash hex 64 karakter menjadi byte mentah; `None` jika formatnya tidak valid.
+pub fn decode_hash_hex(hex: &str) -> Option<[u8; 32]> {
+    decode_hex(hex)?.try_into().ok()
+}
+
+/// `meets_difficulty` untuk hash yang tersimpan sebagai string hex.
+pub fn hash_meets_difficulty(hash: &str, bits: u32) -> bool {
+    decode_hash_hex(hash).is_some_and(|bytes| meets_difficulty(&bytes, bits))
+}
+
+/// Satu langkah bukti Merkle: hash saudara dan apakah saudara itu berada di kiri.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MerkleProofStep {
+    pub sibling: String,
+    pub is_left: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct MerkleTree {
+    leaves: Vec<String>,
+    /// Seluruh level hasil `build_tree`, dari hash leaf sampai root; kosong sebelum dibangun.
+    levels: Vec<Vec<String>>,
+}
+
+impl MerkleTree {
+    /// Membuat Merkle Tree dari id transaksi; `MerkleTree::new` membangunnya langsung dari `Transaction`.
+    pub fn from_leaves(leaves: Vec<String>) -> Self {
+        Self { leaves, levels: Vec::new() }
+    }
+
+    /// Membangun tree dan mengembalikan root hash. Level-level disimpan, sehingga pemanggilan
+    /// berikutnya maupun `generate_proof` tidak meng-hash ulang.
+    ///
+    /// Leaf di-hash dengan prefix `0x00` dan node internal dengan `0x01` sehingga leaf tidak bisa
+    /// disamarkan sebagai node internal. Node terakhir pada level ganjil dinaikkan apa adanya,
+    /// bukan diduplikasi, agar daftar `[a, b, c]` dan `[a, b, c, c]` tidak menghasilkan root yang sama
+    /// (malleability ala CVE-2012-2459).
+    pub fn build_tree(&mut self) -> String {
+        if self.levels.is_empty() && !self.leaves.is_empty() {
+            let mut current_level: Vec<String> = self.leaves.iter().map(|leaf| Self::hash_node(0x00, &[leaf])).collect();
+            while current_level.len() > 1 {
+                let next_level = current_level
+                    .chunks(2)
+                    .map(|pair| match pair {
+                        [left, right] => Self::hash_node(0x01, &[left, right]),
+                        [single] => single.clone(),
+                        _ => unreachable!(),
+                    })
+                    .collect();
+                self.levels.push(std::mem::replace(&mut current_level, next_level));
+            }
+            self.levels.push(current_level);
+        }
+        self.root().unwrap_or_else(|| "0".repeat(64))
+    }
+
+    /// Root dari tree yang sudah dibangun; `None` sebelum `build_tree` atau tanpa transaksi.
+    pub fn root(&self) -> Option<String> {
+        self.levels.last().and_then(|level| level.first()).cloned()
+    }
+
+    /// Bukti inklusi untuk leaf ke-`index`, disusun dari level yang tersimpan
+    /// (tree dibangun lebih dulu jika belum). Level tanpa saudara (node ganjil terakhir) dilewati.
+    pub fn generate_proof(&mut self, index: usize) -> Option<Vec<MerkleProofStep>> {
+        self.build_tree();
+        if index >= self.levels.first()?.len() {
+            return None;
+        }
+        let mut position = index;
+        let mut proof = Vec::new();
+        for level in &self.levels[..self.levels.len() - 1] {
+            let sibling = position ^ 1;
+            if let Some(hash) = level.get(sibling) {
+                proof.push(MerkleProofStep { sibling: hash.clone(), is_left: sibling < position });
+            }
+            position /= 2;
+        }
+        Some(proof)
+    }
+
+    /// Memeriksa bahwa transaksi dengan id `leaf` termasuk dalam tree ber-root `root`.
+    pub fn verify_proof(leaf: &str, proof: &[MerkleProofStep], root: &str) -> bool {
+        let leaf = leaf.to_string();
+        let hash = proof.iter().fold(Self::hash_node(0x00, &[&leaf]), |hash, step| {
+            if step.is_left {
+                Self::hash_node(0x01, &[&step.sibling, &hash])
+            } else {
+                Self::hash_node(0x01, &[&hash, &step.sibling])
+            }
+        });
+        hash == root
+    }
+
+    /// Hash SHA-256 dari `prefix` domain diikuti bagian-bagian node.
+    fn hash_node(prefix: u8, parts: &[&String]) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update([prefix]);
+        for part in parts {
+            hasher.update(part.as_bytes());
+        }
+        format!("{:x}", hasher.finalize())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_block_hash_is_deterministic_for_fixed_fields() {
+        let preimage = block_hash_preimage(1, 1_700_000_000_000, "prev", "root", 42, 4);
+        assert_eq!(preimage, "11700000000000prevroot424");
+        assert_eq!(sha256_hex(&preimage), sha256_hex("11700000000000prevroot424"));
+        assert_eq!(sha256_hex(""), "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855");
+    }
+
+    #[test]
+    fn test_length_prefixed_keeps_field_boundaries() {
+        assert_eq!(length_prefixed(&["ab", "", "12"]), "2:ab0:2:12");
+        assert_ne!(length_prefixed(&["12", "3"]), length_prefixed(&["1", "23"]));
+    }
+
+    #[test]
+    fn test_merkle_proof_reuses_built_levels() {
+        let ids: Vec<String> = (1..=5).map(|i| sha256_hex(&i.to_string())).collect();
+        let mut tree = MerkleTree::from_leaves(ids.clone());
+        let root = tree.build_tree();
+
+        // Leaf dikosongkan: bukti hanya bisa lahir dari level yang sudah tersimpan, bukan dari hashing ulang.
+        tree.leaves.clear();
+        assert_eq!(tree.build_tree(), root);
+        for (index, id) in ids.iter().enumerate() {
+            let proof = tree.generate_proof(index).unwrap();
+            assert!(MerkleTree::verify_proof(id, &proof, &root), "Bukti leaf #{}", index);
+        }
+        assert!(tree.generate_proof(ids.len()).is_none());
+
+        let proof = tree.generate_proof(0).unwrap();
+        assert!(!MerkleTree::verify_proof(&ids[1], &proof, &root));
+    }
+}
@@ -6,20 +6,250 @@ use std::time::{Duration, Instant};
 use rayon::prelude::*;
 use indicatif::{ProgressBar, ProgressStyle};
 use std::sync::atomic::{AtomicBool, Ordering, AtomicU64};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex, Once, OnceLock, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use std::ffi::OsString;
+use std::collections::{BTreeMap, BinaryHeap, HashMap, HashSet};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::net::SocketAddr;
+use std::ops::Range;
+use std::sync::mpsc::{self, Receiver, Sender};
+use bincode::Options;
+use ed25519_dalek::SigningKey;
+pub use hashing::{difficulty_target, meets_difficulty, MerkleProofStep, MerkleTree, MAX_DIFFICULTY};
+use utxo::TxOutput;
+use hashing::{block_hash_preimage, decode_hash_hex, decode_hex, encode_hex, hash_meets_difficulty, length_prefixed, sha256_hex};
+
+#[cfg(feature = "server")]
+mod server;
+pub mod hashing;
+pub mod p2p;
+pub mod signature;
+pub mod utxo;
 
 // --- Konstanta & Konfigurasi ---
-const INITIAL_DIFFICULTY: u32 = 15; // Kesulitan awal yang lebih menantang
+const INITIAL_DIFFICULTY: u32 = 20; // Jumlah bit nol di depan hash (~1 juta hash per blok)
 const BLOCK_TIME_SECONDS: u64 = 10; // Target waktu per blok
 const DIFFICULTY_ADJUSTMENT_INTERVAL: u64 = 10; // Penyesuaian setiap 10 blok
 const INITIAL_REWARD: u64 = 50; // Hadiah awal
 const HALVING_INTERVAL: u64 = 20; // Halving setiap 20 blok (untuk demonstrasi)
+const MAX_TRANSACTIONS_PER_BLOCK: usize = 1000; // Batas transaksi non-coinbase per blok
+const MAX_MEMPOOL_SIZE: usize = 5000; // Batas transaksi yang menunggu di mempool
+const COINBASE_MATURITY: u64 = 0; // Blok yang harus ditambang di atas coinbase sebelum bisa dibelanjakan
+const PROGRESS_UPDATE_INTERVAL: Duration = Duration::from_millis(100); // Jeda minimum antar update progress bar
+const MIN_ADJUSTED_DIFFICULTY: u32 = 1; // Batas bawah hasil retarget
+const MAX_ADJUSTED_DIFFICULTY: u32 = 64; // Batas atas hasil retarget (~1.8e19 hash per blok)
+const MAX_ORPHAN_BLOCKS: usize = 100; // Batas blok yang menunggu induknya; yang tertua dibuang lebih dulu
+const NONCE_CHUNK_SIZE: u64 = 1 << 20; // Nonce per putaran mining paralel; batas checkpoint saat interupsi
+const NETWORK_MAGIC: [u8; 4] = *b"RLGC"; // Penanda awal frame blok di jaringan
+const NO_PROGRESS_ENV: &str = "RELOGICCHAIN_NO_PROGRESS"; // Matikan progress bar, mis. untuk log CI
+
+// --- Parameter Konsensus ---
+/// Parameter ekonomi dan difficulty yang dibaca rantai; `default()` sama dengan konstanta di atas.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct ConsensusParams {
+    pub initial_difficulty: u32,
+    pub block_time_seconds: u64,
+    pub difficulty_adjustment_interval: u64,
+    pub initial_reward: u64,
+    pub halving_interval: u64,
+    pub difficulty_rate_limit: Option<DifficultyRateLimit>,
+    /// Jumlah maksimum transaksi per blok, tidak termasuk coinbase.
+    pub max_transactions_per_block: usize,
+    /// Rentang yang selalu membatasi hasil `adjust_difficulty`.
+    pub min_difficulty: u32,
+    pub max_difficulty: u32,
+    /// Jumlah maksimum transaksi di mempool; saat penuh, fee terendah dikeluarkan lebih dulu.
+    pub max_mempool_size: usize,
+    /// Coinbase pada tinggi `h` baru bisa dibelanjakan saat tip mencapai `h + coinbase_maturity`.
+    pub coinbase_maturity: u64,
+}
+
+/// Batas perubahan difficulty kumulatif: dalam jendela `window_retargets` kali interval
+/// penyesuaian, difficulty tidak boleh naik/turun lebih dari `max_factor` kali.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct DifficultyRateLimit {
+    pub window_retargets: u64,
+    pub max_factor: f64,
+}
+
+impl Default for ConsensusParams {
+    fn default() -> Self {
+        Self {
+            initial_difficulty: INITIAL_DIFFICULTY,
+            block_time_seconds: BLOCK_TIME_SECONDS,
+            difficulty_adjustment_interval: DIFFICULTY_ADJUSTMENT_INTERVAL,
+            initial_reward: INITIAL_REWARD,
+            halving_interval: HALVING_INTERVAL,
+            difficulty_rate_limit: None,
+            max_transactions_per_block: MAX_TRANSACTIONS_PER_BLOCK,
+            min_difficulty: MIN_ADJUSTED_DIFFICULTY,
+            max_difficulty: MAX_ADJUSTED_DIFFICULTY,
+            max_mempool_size: MAX_MEMPOOL_SIZE,
+            coinbase_maturity: COINBASE_MATURITY,
+        }
+    }
+}
+
+impl ConsensusParams {
+    /// Memuat konfigurasi dari berkas TOML lalu memvalidasinya.
+    pub fn from_toml_file(path: &Path) -> Result<Self, ConfigError> {
+        let contents = std::fs::read_to_string(path).map_err(ConfigError::Io)?;
+        let config: ConsensusParams = toml::from_str(&contents).map_err(ConfigError::Parse)?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Menolak parameter yang akan merusak rantai (pembagian nol, indeks invalid, dll).
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        if self.difficulty_adjustment_interval == 0 {
+            return Err(ConfigError::ZeroDifficultyAdjustmentInterval);
+        }
+        if self.halving_interval == 0 {
+            return Err(ConfigError::ZeroHalvingInterval);
+        }
+        if self.block_time_seconds == 0 {
+            return Err(ConfigError::ZeroBlockTime);
+        }
+        if self.max_mempool_size == 0 {
+            return Err(ConfigError::ZeroMempoolSize);
+        }
+        if self.min_difficulty == 0 || self.min_difficulty > self.max_difficulty || self.max_difficulty > MAX_DIFFICULTY {
+            return Err(ConfigError::InvalidDifficultyBounds { min: self.min_difficulty, max: self.max_difficulty });
+        }
+        if !(self.min_difficulty..=self.max_difficulty).contains(&self.initial_difficulty) {
+            return Err(ConfigError::InitialDifficultyOutOfBounds {
+                difficulty: self.initial_difficulty,
+                min: self.min_difficulty,
+                max: self.max_difficulty,
+            });
+        }
+        if let Some(limit) = &self.difficulty_rate_limit {
+            if limit.window_retargets == 0 || !limit.max_factor.is_finite() || limit.max_factor < 1.0 {
+                return Err(ConfigError::InvalidDifficultyRateLimit);
+            }
+        }
+        Ok(())
+    }
+}
 
 // --- Error Handling ---
 #[derive(Debug)]
 pub enum MiningError {
     Interrupted,
     NoValidNonceFound,
+    InvalidDifficulty(u32),
+    InvalidTarget(String),
+    Timeout,
+}
+
+#[derive(Debug)]
+pub enum ChainError {
+    Mining(MiningError),
+    SupplyOverflow,
+    NotExtendingTip,
+    UnexpectedIndex { expected: u64, found: u64 },
+    InvalidProofOfWork,
+    InvalidGenesis,
+    GenesisRewardMismatch { expected: u64, found: u64 },
+    InconsistentTip { height: u64, tip_index: u64 },
+    ChainNotEmpty { height: u64 },
+    TooManyTransactions { limit: usize, found: usize },
+    DuplicateTransaction(String),
+    Config(ConfigError),
+    Validation(ValidationError),
+}
+
+/// Kerusakan yang ditemukan saat memvalidasi rantai; `index` adalah posisi blok di rantai.
+#[derive(Debug, PartialEq)]
+pub enum ValidationError {
+    HashMismatch { index: u64 },
+    BrokenLink { index: u64 },
+    BadProofOfWork { index: u64 },
+    MerkleMismatch { index: u64 },
+    IndexMismatch { index: u64, found: u64 },
+    UnexpectedDifficulty { index: u64, expected: u32, found: u32 },
+    InvalidTransaction { index: u64, error: TxError },
+    RangeOutOfBounds { start: u64, end: u64 },
+    TimestampRegression { index: u64 },
+    InvalidCoinbase { index: u64 },
+    DoubleSpend { index: u64, tx_id: String },
+    TooManyTransactions { index: u64, limit: usize, found: usize },
+    DuplicateTransaction { index: u64, tx_id: String },
+    InvalidGenesis,
+    /// Body blok yang dipangkas tidak bisa dimuat dari penyimpanan `warm_start`.
+    MissingBody { index: u64 },
+    /// Saldo hasil replay body yang dipangkas berbeda dengan snapshot `pruned_balances`.
+    SnapshotMismatch { index: u64 },
+}
+
+#[derive(Debug, PartialEq)]
+pub enum TxError {
+    UnexpectedCoinbase,
+    InsufficientBalance { balance: i64, required: u64 },
+    ZeroAmount,
+    InvalidSignature,
+    Duplicate,
+    MempoolFull { min_fee: u64 },
+    EmptyAddress,
+    SelfTransfer,
+    IdMismatch,
+    UnexpectedOutputs,
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    ZeroDifficultyAdjustmentInterval,
+    ZeroHalvingInterval,
+    ZeroBlockTime,
+    ZeroMempoolSize,
+    InvalidDifficultyRateLimit,
+    InvalidDifficultyBounds { min: u32, max: u32 },
+    InitialDifficultyOutOfBounds { difficulty: u32, min: u32, max: u32 },
+    AllocationOverflow,
+    InvalidCoinbaseRecipients,
+    InvalidArgument { flag: &'static str, value: String },
+    Io(std::io::Error),
+    Parse(toml::de::Error),
+}
+
+#[derive(Debug)]
+pub enum StorageError {
+    Io(std::io::Error),
+    Json(serde_json::Error),
+    Bincode(bincode::Error),
+    Config(ConfigError),
+    EmptyChain,
+    SupplyOverflow,
+    /// Body blok tidak ada di memori maupun penyimpanan, atau isinya tidak cocok dengan merkle root.
+    BodyUnavailable { index: u64 },
+    Invalid(ChainError),
+}
+
+#[derive(Debug)]
+pub enum NetError {
+    WrongMagic,
+    Truncated,
+    Decode(bincode::Error),
+}
+
+impl From<MiningError> for ChainError {
+    fn from(err: MiningError) -> Self {
+        ChainError::Mining(err)
+    }
+}
+
+impl From<ValidationError> for ChainError {
+    fn from(err: ValidationError) -> Self {
+        ChainError::Validation(err)
+    }
+}
+
+impl From<ConfigError> for ChainError {
+    fn from(err: ConfigError) -> Self {
+        ChainError::Config(err)
+    }
 }
 
 // --- Transaction ---
@@ -29,42 +259,199 @@ pub struct Transaction {
     pub from: String,
     pub to: String,
     pub amount: u64,
+    #[serde(default)]
+    pub fee: u64,
     pub timestamp: u64,
     pub signature: String,
+    /// Tag skema signature untuk memilih `signature::Verifier`; transaksi lama tanpa field ini adalah ed25519.
+    #[serde(default = "default_signature_scheme")]
+    pub scheme: String,
+    /// Penerima tambahan setelah `to`, hanya untuk coinbase yang hadiahnya dibagi
+    /// (lihat `Blockchain::set_coinbase_recipients`). Transaksi biasa selalu kosong.
+    #[serde(default)]
+    pub extra_outputs: Vec<TxOutput>,
+}
+
+fn default_signature_scheme() -> String {
+    signature::ED25519.to_string()
 }
 
 impl Transaction {
     /// Membuat transaksi baru.
     pub fn new(from: String, to: String, amount: u64, signature: String) -> Self {
         let timestamp = Utc::now().timestamp_millis() as u64;
+        Self::with_timestamp(from, to, amount, signature, timestamp)
+    }
+
+    /// Membuat transaksi dengan timestamp tertentu, mis. untuk impor atau rekonstruksi riwayat.
+    pub fn with_timestamp(from: String, to: String, amount: u64, signature: String, timestamp: u64) -> Self {
         let mut transaction = Self {
             id: String::new(),
             from,
             to,
             amount,
+            fee: 0,
             timestamp,
             signature,
+            scheme: default_signature_scheme(),
+            extra_outputs: Vec::new(),
         };
         transaction.id = transaction.calculate_hash();
         transaction
     }
 
+    /// Menetapkan fee untuk miner dan menghitung ulang `id`. Panggil sebelum `sign`,
+    /// karena fee ikut ditandatangani.
+    pub fn with_fee(mut self, fee: u64) -> Self {
+        self.fee = fee;
+        self.id = self.calculate_hash();
+        self
+    }
+
     /// Menghitung hash dari transaksi.
     pub fn calculate_hash(&self) -> String {
-        let mut hasher = Sha256::new();
-        let record = format!("{}{}{}{}{}", self.from, self.to, self.amount, self.timestamp, self.signature);
-        hasher.update(record.as_bytes());
-        format!("{:x}", hasher.finalize())
+        sha256_hex(&self.hash_preimage())
+    }
+
+    /// String persis yang di-hash oleh `calculate_hash`: from, to, amount, fee, timestamp, signature,
+    /// tag skema, lalu pasangan penerima dan nilai dari `extra_outputs`, masing-masing dengan awalan
+    /// panjang agar batas antar field tidak ambigu. Skema ikut di-hash sehingga merkle root dan hash
+    /// blok juga mengomit skema yang dipakai.
+    pub fn hash_preimage(&self) -> String {
+        let (amount, fee, timestamp) = (self.amount.to_string(), self.fee.to_string(), self.timestamp.to_string());
+        let mut preimage = length_prefixed(&[&self.from, &self.to, &amount, &fee, &timestamp, &self.signature, &self.scheme]);
+        for output in &self.extra_outputs {
+            preimage.push_str(&length_prefixed(&[&output.to, &output.amount.to_string()]));
+        }
+        preimage
     }
 
     /// Membuat transaksi coinbase untuk hadiah mining.
     pub fn coinbase(to: String, amount: u64) -> Self {
         Transaction::new("coinbase".to_string(), to, amount, "".to_string())
     }
+
+    /// Coinbase yang membagi `amount` ke `recipients` sesuai bobotnya. Pembulatan ke bawah setiap
+    /// bagian menyisakan sisa kecil yang diberikan ke penerima pertama, sehingga totalnya tetap
+    /// `amount`. Penerima dengan bagian 0 tidak dibuatkan output.
+    pub fn split_coinbase(recipients: &[(String, u32)], amount: u64) -> Self {
+        let total_weight: u128 = recipients.iter().map(|(_, weight)| u128::from(*weight)).sum();
+        let shares: Vec<(String, u64)> = recipients
+            .iter()
+            .map(|(address, weight)| (address.clone(), (u128::from(amount) * u128::from(*weight) / total_weight.max(1)) as u64))
+            .collect();
+        let remainder = amount - shares.iter().map(|(_, share)| share).sum::<u64>();
+        let mut shares = shares.into_iter();
+        let (first, first_share) = shares.next().expect("Daftar penerima coinbase tidak boleh kosong");
+        let mut coinbase = Transaction::coinbase(first, first_share + remainder);
+        coinbase.extra_outputs = shares.filter(|(_, share)| *share > 0).map(|(to, amount)| TxOutput { to, amount }).collect();
+        coinbase.id = coinbase.calculate_hash();
+        coinbase
+    }
+
+    /// Setiap penerima beserta nilainya: `to` lebih dulu, lalu `extra_outputs`.
+    pub fn outputs(&self) -> impl Iterator<Item = (&str, u64)> {
+        std::iter::once((self.to.as_str(), self.amount)).chain(self.extra_outputs.iter().map(|output| (output.to.as_str(), output.amount)))
+    }
+
+    /// Total nilai yang diterima `address` dari transaksi ini.
+    pub fn amount_received_by(&self, address: &str) -> u64 {
+        self.outputs().filter(|(to, _)| *to == address).fold(0u64, |sum, (_, amount)| sum.saturating_add(amount))
+    }
+
+    /// Total nilai yang dipindahkan transaksi; acuan tunggal untuk perhitungan saldo dan supply.
+    pub fn total_output_amount(&self) -> u64 {
+        self.outputs().fold(0u64, |sum, (_, amount)| sum.saturating_add(amount))
+    }
+
+    /// Total yang dipotong dari saldo pengirim: nilai yang dipindahkan ditambah fee.
+    pub fn total_debit(&self) -> u64 {
+        self.total_output_amount().saturating_add(self.fee)
+    }
+
+    /// Mengecek apakah transaksi ini adalah transaksi coinbase.
+    pub fn is_coinbase(&self) -> bool {
+        self.from == "coinbase"
+    }
+
+    /// Hash yang ditandatangani: tag skema, from, to, amount, fee, dan timestamp (tanpa signature itu
+    /// sendiri), masing-masing dengan awalan panjang. Tag ikut ditandatangani agar tidak bisa ditukar
+    /// ke skema lain, dan awalan panjang mencegah nilai digeser antar field tanpa membatalkan signature.
+    fn signing_hash(&self) -> [u8; 32] {
+        let (amount, fee, timestamp) = (self.amount.to_string(), self.fee.to_string(), self.timestamp.to_string());
+        let message = length_prefixed(&[&self.scheme, &self.from, &self.to, &amount, &fee, &timestamp]);
+        Sha256::digest(message.as_bytes()).into()
+    }
+
+    /// Menandatangani transaksi dengan ed25519 dan menyimpan signature sebagai hex.
+    /// `id` dihitung ulang karena signature ikut di-hash.
+    pub fn sign(&mut self, keypair: &SigningKey) {
+        self.sign_with(keypair);
+    }
+
+    /// Seperti `sign`, tetapi dengan skema apa pun; tag skema `signer` ikut disimpan di transaksi.
+    pub fn sign_with(&mut self, signer: &impl signature::Signer) {
+        self.scheme = signer.scheme().to_string();
+        self.signature = signer.sign(&self.signing_hash());
+        self.id = self.calculate_hash();
+    }
+
+    /// Memverifikasi signature dengan `from` sebagai public key (hex) lewat verifier bawaan.
+    /// Coinbase tidak punya pengirim sehingga selalu lolos.
+    pub fn verify_signature(&self) -> bool {
+        self.verify_signature_with(signature::default_verifiers())
+    }
+
+    /// Seperti `verify_signature`, tetapi memilih dari `verifiers` yang tag-nya sama dengan `scheme`.
+    /// Skema yang tidak ada di daftar selalu ditolak.
+    pub fn verify_signature_with(&self, verifiers: &[&dyn signature::Verifier]) -> bool {
+        if self.is_coinbase() {
+            return true;
+        }
+        verifiers
+            .iter()
+            .find(|verifier| verifier.scheme() == self.scheme)
+            .is_some_and(|verifier| verifier.verify(&self.from, &self.signing_hash(), &self.signature))
+    }
+
+    /// JSON transaksi yang mudah dibaca, untuk tooling dan debugging.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("Transaksi selalu dapat diserialisasi")
+    }
+
+    /// Menolak output bernilai 0 yang hanya menjadi spam. Coinbase dikecualikan
+    /// karena hadiahnya bisa 0 setelah halving habis.
+    pub fn validate_amount_nonzero_for_outputs(&self) -> Result<(), TxError> {
+        if !self.is_coinbase() && self.total_output_amount() == 0 {
+            return Err(TxError::ZeroAmount);
+        }
+        Ok(())
+    }
+
+    /// Pemeriksaan mandiri tanpa state rantai: alamat tidak kosong, nilai bukan 0, pengirim
+    /// berbeda dari penerima (kecuali coinbase), dan `id` cocok dengan `calculate_hash`.
+    /// Cek terakhir menangkap transaksi dari jaringan yang id-nya dipalsukan.
+    pub fn validate(&self) -> Result<(), TxError> {
+        if self.from.is_empty() || self.outputs().any(|(to, _)| to.is_empty()) {
+            return Err(TxError::EmptyAddress);
+        }
+        // Signature tidak mencakup `extra_outputs`, jadi hanya coinbase yang boleh memakainya.
+        if !self.is_coinbase() && !self.extra_outputs.is_empty() {
+            return Err(TxError::UnexpectedOutputs);
+        }
+        self.validate_amount_nonzero_for_outputs()?;
+        if !self.is_coinbase() && self.from == self.to {
+            return Err(TxError::SelfTransfer);
+        }
+        if self.id != self.calculate_hash() {
+            return Err(TxError::IdMismatch);
+        }
+        Ok(())
+    }
 }
 
 // --- Block ---
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct Block {
     pub index: u64,
     pub timestamp: u64,
@@ -77,9 +464,14 @@ pub struct Block {
 }
 
 impl Block {
-    /// Membuat instance blok baru (tanpa hash).
+    /// Membuat instance blok baru (tanpa hash) dengan timestamp saat ini.
     fn new(index: u64, previous_hash: String, difficulty: u32, transactions: Vec<Transaction>) -> Self {
         let timestamp = Utc::now().timestamp_millis() as u64;
+        Self::with_timestamp(index, previous_hash, difficulty, transactions, timestamp)
+    }
+
+    /// Seperti `new`, tetapi dengan timestamp tertentu sehingga hasilnya deterministik.
+    pub fn with_timestamp(index: u64, previous_hash: String, difficulty: u32, transactions: Vec<Transaction>, timestamp: u64) -> Self {
         let merkle_root = MerkleTree::new(&transactions).build_tree();
         
         Self {
@@ -94,317 +486,4740 @@ impl Block {
         }
     }
 
-    /// Menghitung hash untuk blok.
-    pub fn calculate_hash(&self) -> String {
-        let mut hasher = Sha256::new();
-        let record = format!("{}{}{}{}{}{}", self.index, self.timestamp, self.previous_hash, self.merkle_root, self.nonce, self.difficulty);
-        hasher.update(record.as_bytes());
-        format!("{:x}", hasher.finalize())
+    /// Memulai penyusunan blok secara fluent.
+    pub fn builder() -> BlockBuilder {
+        BlockBuilder::default()
     }
-}
-
-// --- Merkle Tree ---
-#[derive(Debug, Clone)]
-pub struct MerkleTree {
-    root: Option<String>,
-    leaves: Vec<String>,
-}
 
-impl MerkleTree {
-    /// Membuat Merkle Tree baru dari transaksi.
-    pub fn new(transactions: &[Transaction]) -> Self {
-        let leaves = transactions.iter().map(|tx| tx.id.clone()).collect();
-        Self { root: None, leaves }
+    /// Menghitung hash untuk blok.
+    pub fn calculate_hash(&self) -> String {
+        sha256_hex(&self.hash_preimage())
     }
 
-    /// Membangun tree dan mengembalikan root hash.
-    pub fn build_tree(&mut self) -> String {
-        if self.leaves.is_empty() {
-            return "0".repeat(64);
-        }
-        if self.leaves.len() == 1 {
-            return self.leaves[0].clone();
-        }
-
-        let mut current_level = self.leaves.clone();
-        while current_level.len() > 1 {
-            if current_level.len() % 2 != 0 {
-                current_level.push(current_level.last().unwrap().clone());
-            }
-            
-            let mut next_level = Vec::new();
-            for i in (0..current_level.len()).step_by(2) {
-                let left = &current_level[i];
-                let right = &current_level[i+1];
-                let mut hasher = Sha256::new();
-                hasher.update(left.as_bytes());
-                hasher.update(right.as_bytes());
-                next_level.push(format!("{:x}", hasher.finalize()));
+    /// Memeriksa transaksi blok secara berurutan terhadap saldo pada blok induk.
+    /// Coinbase di posisi pertama hanya mengkredit penerima; transaksi lain tidak boleh overspend.
+    pub fn validate_against_balances(&self, balances: &HashMap<String, i64>) -> Result<HashMap<String, i64>, TxError> {
+        let mut balances = balances.clone();
+        for (position, tx) in self.transactions.iter().enumerate() {
+            // Selain hadiah di posisi pertama, coinbase hanya sah sebagai alokasi awal di genesis.
+            if tx.is_coinbase() && (position == 0 || self.index == 0) {
+                Blockchain::credit_outputs(&mut balances, tx);
+            } else {
+                Blockchain::apply_to_balances(&mut balances, tx)?;
             }
-            current_level = next_level;
         }
-        self.root = Some(current_level.remove(0));
-        self.root.as_ref().unwrap().clone()
+        Ok(balances)
     }
-}
 
-// --- Blockchain ---
-#[derive(Debug)]
-pub struct Blockchain {
-    pub blocks: Vec<Block>,
-    pub pending_transactions: Vec<Transaction>,
-    miner_address: String,
-    total_supply: u64,
-}
+    /// Jumlah fee dari transaksi non-coinbase di blok ini.
+    pub fn total_fees(&self) -> u64 {
+        self.transactions.iter().filter(|tx| !tx.is_coinbase()).fold(0u64, |sum, tx| sum.saturating_add(tx.fee))
+    }
 
-impl Blockchain {
-    /// Membuat blockchain baru.
-    pub fn new(miner_address: String) -> Self {
-        let mut chain = Self {
-            blocks: Vec::new(),
-            pending_transactions: Vec::new(),
-            miner_address,
-            total_supply: 0,
+    /// Koin baru yang dicetak blok: nilai coinbase dikurangi fee yang hanya berpindah tangan.
+    /// Untuk genesis, alokasi awal ikut dihitung.
+    pub fn minted_amount(&self) -> u64 {
+        let coinbase = if self.index == 0 {
+            self.transactions.iter().filter(|tx| tx.is_coinbase()).fold(0u64, |sum, tx| sum.saturating_add(tx.total_output_amount()))
+        } else {
+            self.transactions.first().filter(|tx| tx.is_coinbase()).map_or(0, Transaction::total_output_amount)
         };
-        let mut genesis_block = chain.create_genesis_block();
-        mine_block(&mut genesis_block, INITIAL_DIFFICULTY).expect("Gagal menambang blok genesis");
-        chain.total_supply += chain.get_reward(0);
-        chain.blocks.push(genesis_block);
-        chain
+        coinbase.saturating_sub(self.total_fees())
     }
 
-    /// Membuat blok genesis.
-    fn create_genesis_block(&self) -> Block {
-        let reward = self.get_reward(0);
-        let coinbase_tx = Transaction::coinbase(self.miner_address.clone(), reward);
-        Block::new(0, "0".repeat(64), INITIAL_DIFFICULTY, vec![coinbase_tx])
+    /// JSON blok yang mudah dibaca, untuk tooling dan debugging.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("Blok selalu dapat diserialisasi")
     }
 
-    /// Menghitung hadiah mining berdasarkan tinggi blok.
-    pub fn get_reward(&self, block_index: u64) -> u64 {
-        let halvings = block_index / HALVING_INTERVAL;
-        INITIAL_REWARD / (2u64.pow(halvings as u32))
+    /// Membaca blok dari JSON hasil `to_json` (atau format serde lain yang setara).
+    pub fn from_json(json: &str) -> Result<Block, serde_json::Error> {
+        serde_json::from_str(json)
     }
 
-    /// Menyesuaikan kesulitan mining.
-    pub fn adjust_difficulty(&self) -> u32 {
-        if self.blocks.len() < DIFFICULTY_ADJUSTMENT_INTERVAL as usize {
-            return self.blocks.last().unwrap().difficulty;
+    /// Menyusun frame jaringan: `[magic:4][len:4 big-endian][payload bincode]`.
+    pub fn serialize_for_network(&self) -> Vec<u8> {
+        let payload = bincode::serialize(self).expect("Blok selalu dapat diserialisasi");
+        let mut frame = Vec::with_capacity(8 + payload.len());
+        frame.extend_from_slice(&NETWORK_MAGIC);
+        frame.extend_from_slice(&(payload.len() as u32).to_be_bytes());
+        frame.extend_from_slice(&payload);
+        frame
+    }
+
+    /// Membaca satu frame dari awal `bytes`. Magic dan panjang diperiksa sebelum decode;
+    /// mengembalikan blok dan jumlah byte yang dipakai agar sisa stream bisa dibaca berikutnya.
+    pub fn deserialize_from_network(bytes: &[u8]) -> Result<(Block, usize), NetError> {
+        if bytes.len() < 8 {
+            return Err(NetError::Truncated);
         }
-        
-        let last_adjustment_block = &self.blocks[self.blocks.len() - DIFFICULTY_ADJUSTMENT_INTERVAL as usize];
-        let current_block = self.blocks.last().unwrap();
-        
-        let time_taken = current_block.timestamp - last_adjustment_block.timestamp;
-        let expected_time = (DIFFICULTY_ADJUSTMENT_INTERVAL * BLOCK_TIME_SECONDS * 1000) as u64;
+        if bytes[..4] != NETWORK_MAGIC {
+            return Err(NetError::WrongMagic);
+        }
+        let len = u32::from_be_bytes([bytes[4], bytes[5], bytes[6], bytes[7]]) as usize;
+        let end = 8usize.checked_add(len).ok_or(NetError::Truncated)?;
+        let payload = bytes.get(8..end).ok_or(NetError::Truncated)?;
+        let block = bincode::deserialize(payload).map_err(NetError::Decode)?;
+        Ok((block, end))
+    }
 
-        let time_ratio = expected_time as f64 / time_taken as f64;
-        let old_difficulty = current_block.difficulty as f64;
+    /// String persis yang di-hash oleh `calculate_hash`:
+    /// index, timestamp, previous_hash, merkle_root, nonce, difficulty.
+    pub fn hash_preimage(&self) -> String {
+        block_hash_preimage(self.index, self.timestamp, &self.previous_hash, &self.merkle_root, self.nonce, self.difficulty)
+    }
 
-        // Batasi perubahan difficulty (misal, max 4x)
-        let new_difficulty = if time_ratio > 4.0 {
-            old_difficulty * 4.0
-        } else if time_ratio < 0.25 {
-            old_difficulty * 0.25
-        } else {
-            old_difficulty * time_ratio
-        };
-        
-        // Batasi difficulty minimum
-        (new_difficulty.round() as u32).max(1)
+    /// Preimage hash yang dipisah di sekitar nonce: `(index..merkle_root, difficulty)`.
+    /// `prefix + nonce + suffix` sama persis dengan `hash_preimage`, sehingga mining cukup
+    /// menyusun bagian ini sekali dan tidak perlu meng-clone blok untuk setiap nonce.
+    pub fn header_without_nonce(&self) -> (String, String) {
+        let prefix = format!("{}{}{}{}", self.index, self.timestamp, self.previous_hash, self.merkle_root);
+        (prefix, self.difficulty.to_string())
     }
 
-    /// Menambang blok baru dan menambahkannya ke rantai.
-    pub fn mine_and_add_block(&mut self) -> Result<(), MiningError> {
-        let reward = self.get_reward(self.blocks.len() as u64);
-        let mut transactions = self.pending_transactions.drain(..).collect::<Vec<_>>();
-        transactions.insert(0, Transaction::coinbase(self.miner_address.clone(), reward));
+    /// Mengambil header blok (semua field kecuali transaksi).
+    pub fn header(&self) -> BlockHeader {
+        BlockHeader {
+            index: self.index,
+            timestamp: self.timestamp,
+            previous_hash: self.previous_hash.clone(),
+            hash: self.hash.clone(),
+            merkle_root: self.merkle_root.clone(),
+            nonce: self.nonce,
+            difficulty: self.difficulty,
+        }
+    }
+}
 
-        let difficulty = self.adjust_difficulty();
-        let mut new_block = Block::new(
-            self.blocks.len() as u64,
-            self.blocks.last().unwrap().hash.clone(),
-            difficulty,
-            transactions
-        );
+// --- Block Builder ---
+/// Penyusun blok yang eksplisit; merkle root dihitung saat `build`.
+#[derive(Debug, Clone, Default)]
+pub struct BlockBuilder {
+    index: u64,
+    previous_hash: String,
+    difficulty: u32,
+    timestamp: Option<u64>,
+    transactions: Vec<Transaction>,
+}
 
-        mine_block(&mut new_block, difficulty)?;
-        
-        println!("
-Blok #{} berhasil ditambang!", new_block.index);
-        println!("  Hash: {}", new_block.hash);
-        println!("  Nonce: {}", new_block.nonce);
-        println!("  Kesulitan: {}", new_block.difficulty);
-        println!("  Hadiah: {}", reward);
+impl BlockBuilder {
+    pub fn index(mut self, index: u64) -> Self {
+        self.index = index;
+        self
+    }
 
-        self.total_supply += reward;
-        self.blocks.push(new_block);
-        Ok(())
+    pub fn previous_hash(mut self, previous_hash: impl Into<String>) -> Self {
+        self.previous_hash = previous_hash.into();
+        self
     }
-}
 
-/// Fungsi untuk menambang sebuah blok.
-pub fn mine_block(block: &mut Block, difficulty: u32) -> Result<(), MiningError> {
-    let running = Arc::new(AtomicBool::new(true));
-    let r = running.clone();
-    ctrlc::set_handler(move || {
-        r.store(false, Ordering::SeqCst);
-    }).expect("Gagal memasang handler Ctrl-C");
+    pub fn difficulty(mut self, difficulty: u32) -> Self {
+        self.difficulty = difficulty;
+        self
+    }
 
-    let pb = ProgressBar::new_spinner();
-    pb.enable_steady_tick(Duration::from_millis(100));
-    pb.set_style(ProgressStyle::with_template("{spinner:.green} [{elapsed_precise}] {msg}").unwrap());
-    
-    let hashes_done = Arc::new(AtomicU64::new(0));
-    let start_time = Instant::now();
+    /// Menetapkan timestamp; jika tidak diisi, dipakai waktu saat `build`.
+    pub fn timestamp(mut self, timestamp: u64) -> Self {
+        self.timestamp = Some(timestamp);
+        self
+    }
 
-    let target_prefix = "0".repeat(difficulty as usize);
+    pub fn add_transaction(mut self, tx: Transaction) -> Self {
+        self.transactions.push(tx);
+        self
+    }
 
-    let found_nonce = (0..u64::MAX).into_par_iter().find_any(|&nonce| {
-        if !running.load(Ordering::SeqCst) {
-            return true;
+    /// Membangun blok (belum ditambang, tanpa hash).
+    pub fn build(self) -> Block {
+        match self.timestamp {
+            Some(timestamp) => Block::with_timestamp(self.index, self.previous_hash, self.difficulty, self.transactions, timestamp),
+            None => Block::new(self.index, self.previous_hash, self.difficulty, self.transactions),
         }
-        
-        let mut block_clone = block.clone();
-        block_clone.nonce = nonce;
-        let hash = block_clone.calculate_hash();
-        
-        let hashes = hashes_done.fetch_add(1, Ordering::SeqCst);
-        if hashes % 1000 == 0 { // Update progress bar sesekali
-            let elapsed_secs = start_time.elapsed().as_secs_f64();
-            let hps = hashes as f64 / elapsed_secs.max(1.0);
-            pb.set_message(format!("Mencari... ({} H/s)", hps as u64));
-        }
-
-        hash.starts_with(&target_prefix)
-    });
+    }
+}
 
-    pb.finish_and_clear();
+// --- Block Header ---
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct BlockHeader {
+    pub index: u64,
+    pub timestamp: u64,
+    pub previous_hash: String,
+    pub hash: String,
+    pub merkle_root: String,
+    pub nonce: u64,
+    pub difficulty: u32,
+}
 
-    if !running.load(Ordering::SeqCst) {
-        return Err(MiningError::Interrupted);
+impl BlockHeader {
+    /// Menghitung hash header; hasilnya sama dengan hash blok lengkapnya.
+    pub fn calculate_hash(&self) -> String {
+        sha256_hex(&block_hash_preimage(self.index, self.timestamp, &self.previous_hash, &self.merkle_root, self.nonce, self.difficulty))
     }
+}
 
-    match found_nonce {
-        Some(nonce) => {
-            block.nonce = nonce;
-            block.hash = block.calculate_hash();
-            Ok(())
-        }
-        None => Err(MiningError::NoValidNonceFound),
+// --- Merkle Tree ---
+impl MerkleTree {
+    /// Membuat Merkle Tree baru dari transaksi.
+    pub fn new(transactions: &[Transaction]) -> Self {
+        Self::from_leaves(transactions.iter().map(|tx| tx.id.clone()).collect())
     }
 }
 
-// --- CLI ---
-fn main() {
-    println!("Membuat blockchain baru...");
-    let mut blockchain = Blockchain::new("miner-utama".to_string());
-    println!("Blok Genesis berhasil dibuat.");
-    println!("Hash: {}", blockchain.blocks[0].hash);
-    println!("Total Supply Awal: {}", blockchain.total_supply);
-    println!("---");
-    println!("Tekan Ctrl+C untuk menghentikan mining.");
+// --- Mempool Events ---
+#[derive(Debug, Clone, PartialEq)]
+pub enum RemovalReason {
+    Confirmed,
+    Invalid,
+    Evicted,
+}
 
-    loop {
-        println!("
-Memulai penambangan untuk blok #{}...", blockchain.blocks.len());
-        println!("Kesulitan saat ini: {}", blockchain.adjust_difficulty());
-        println!("Total Supply: {}", blockchain.total_supply);
-        
-        // Tambahkan beberapa transaksi dummy
-        blockchain.pending_transactions.push(Transaction::new("Alice".into(), "Bob".into(), 10, "sig".into()));
-        blockchain.pending_transactions.push(Transaction::new("Charlie".into(), "David".into(), 5, "sig".into()));
+#[derive(Debug, Clone, PartialEq)]
+pub enum MempoolEvent {
+    Added(String),
+    Removed(String, RemovalReason),
+}
 
-        match blockchain.mine_and_add_block() {
-            Ok(_) => {
-                // Lanjutkan loop
-            }
-            Err(MiningError::Interrupted) => {
-                println!("
-Proses mining dihentikan oleh pengguna.");
-                break;
-            }
-            Err(e) => {
-                println!("
-Terjadi error saat mining: {:?}", e);
-                break;
-            }
-        }
+/// Callback untuk setiap blok yang baru ditambahkan ke rantai. Harus `Send + Sync` agar
+/// `Blockchain` tetap bisa dibagi lewat `SharedChain`.
+pub struct BlockCallback(Box<dyn Fn(&Block) + Send + Sync>);
+
+impl std::fmt::Debug for BlockCallback {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("BlockCallback")
     }
 }
 
-// --- Unit Tests ---
-#[cfg(test)]
-mod tests {
-    use super::*;
+// --- Snapshot ---
+/// Tampilan read-only atas rantai pada satu titik waktu. Blok dibagi lewat `Arc`,
+/// sehingga pembacaan panjang (validasi, ekspor) tidak perlu menahan lock rantai.
+#[derive(Debug, Clone)]
+pub struct ChainSnapshot {
+    blocks: Vec<Arc<Block>>,
+}
 
-    #[test]
-    fn test_transaction_hash() {
-        let tx = Transaction::new("a".into(), "b".into(), 10, "s".into());
-        assert!(!tx.id.is_empty());
+impl ChainSnapshot {
+    /// Blok-blok pada saat snapshot diambil.
+    pub fn blocks(&self) -> &[Arc<Block>] {
+        &self.blocks
     }
 
-    #[test]
-    fn test_merkle_tree() {
-        let txs = vec![
-            Transaction::new("a".into(), "b".into(), 1, "s1".into()),
-            Transaction::new("c".into(), "d".into(), 2, "s2".into()),
-        ];
-        let mut tree = MerkleTree::new(&txs);
-        let root = tree.build_tree();
-        assert!(!root.is_empty());
-        assert_ne!(root, "0".repeat(64));
+    /// Header seluruh blok pada snapshot.
+    pub fn headers(&self) -> Vec<BlockHeader> {
+        self.blocks.iter().map(|block| block.header()).collect()
     }
 
-    #[test]
-    fn test_mining_and_valid_proof() {
-        let mut block = Block::new(1, "prev_hash".into(), 5, vec![]);
-        assert!(mine_block(&mut block, 5).is_ok());
-        assert!(block.hash.starts_with(&"0".repeat(5)));
+    /// Jumlah blok pada snapshot.
+    pub fn height(&self) -> u64 {
+        self.blocks.len() as u64
+    }
+
+    /// Blok teratas pada snapshot.
+    pub fn tip(&self) -> &Block {
+        self.blocks.last().expect("Snapshot selalu memiliki blok genesis")
+    }
+}
+
+/// Ringkasan kecil rantai untuk mendeteksi divergensi antar node tanpa mengirim seluruh rantai.
+/// `tip_hash` berbeda pada `height` yang sama menandakan fork.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ChainSummary {
+    pub height: u64,
+    pub tip_hash: String,
+    pub total_work: u128,
+    pub genesis_hash: String,
+}
+
+/// Statistik rantai untuk dashboard, dikembalikan `Blockchain::summary`. Nama `ChainSummary`
+/// sudah dipakai ringkasan deteksi divergensi yang sengaja tidak memuat state lokal seperti mempool.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct ChainStats {
+    pub height: u64,
+    pub tip_hash: String,
+    /// Kesulitan blok berikutnya, sama dengan yang akan dipakai miner.
+    pub difficulty: u32,
+    pub total_supply: u64,
+    pub mempool_size: usize,
+    pub total_work: u128,
+}
+
+/// Ringkasan hasil `compact_mempool`: jumlah transaksi yang dibuang per alasan.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct MempoolCompaction {
+    pub duplicates: usize,
+    pub confirmed: usize,
+    pub invalid: usize,
+}
+
+/// Ringkasan hasil `apply_external_mempool` dan `load_mempool`.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct MempoolSyncReport {
+    pub accepted: usize,
+    pub rejected: usize,
+    pub duplicate: usize,
+}
+
+// --- Penyimpanan ---
+/// Format berkas rantai. `total_supply` sengaja tidak disimpan: nilainya diturunkan ulang
+/// dari coinbase saat dimuat agar berkas yang diubah tidak bisa menggelembungkan supply.
+/// Untuk rantai yang dipangkas, bagian yang dipangkas diwakili `pruned_balances`.
+#[derive(Serialize, Deserialize)]
+struct ChainFile {
+    miner_address: String,
+    #[serde(default)]
+    params: ConsensusParams,
+    blocks: Vec<Arc<Block>>,
+    #[serde(default)]
+    pending_transactions: Vec<Transaction>,
+    #[serde(default)]
+    pruned_height: u64,
+    #[serde(default)]
+    pruned_balances: HashMap<String, i64>,
+    #[serde(default)]
+    pruned_tx_ids: HashSet<String>,
+}
+
+/// Membandingkan dua peta saldo dengan mengabaikan alamat bersaldo 0.
+fn same_nonzero_balances(a: &HashMap<String, i64>, b: &HashMap<String, i64>) -> bool {
+    let nonzero = |balances: &HashMap<String, i64>| balances.iter().filter(|(_, &balance)| balance != 0).count();
+    nonzero(a) == nonzero(b) && a.iter().all(|(address, &balance)| balance == 0 || b.get(address) == Some(&balance))
+}
+
+/// Opsi bincode untuk berkas rantai (encoding varint, lebih ringkas dari default `bincode::serialize`).
+fn bincode_options() -> impl bincode::Options {
+    bincode::DefaultOptions::new()
+}
+
+// --- Validasi ---
+/// Tingkat ketelitian `Blockchain::validate_chain`, dari yang paling ketat.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValidationLevel {
+    /// Semua pemeriksaan: header, merkle root, saldo transaksi, dan genesis.
+    /// Satu-satunya tingkat yang aman untuk menerima rantai sebagai sumber saldo.
+    Full,
+    /// Hanya data yang dikomit header: linkage, PoW, dan jadwal retarget difficulty.
+    /// Body boleh dipangkas; transaksi yang tidak valid (overspend, inflasi coinbase) tidak terdeteksi.
+    HeadersOnly,
+    /// Hanya linkage dan PoW, tanpa memeriksa apakah difficulty mengikuti jadwal retarget.
+    /// Penyerang bisa menyajikan rantai berdifficulty rendah; cocok untuk penyaringan awal saja.
+    PowAndLinkage,
+}
+
+// --- Blockchain ---
+/// Blok di orphan pool beserta waktu diterimanya (milidetik) untuk eviction berbasis TTL.
+#[derive(Debug, Clone)]
+struct OrphanBlock {
+    block: Block,
+    received_at: u64,
+}
+
+#[derive(Debug)]
+pub struct Blockchain {
+    pub blocks: Vec<Arc<Block>>,
+    pub pending_transactions: Vec<Transaction>,
+    miner_address: String,
+    total_supply: u64,
+    params: ConsensusParams,
+    mempool_subscribers: Vec<Sender<MempoolEvent>>,
+    last_mining_duration: Option<Duration>,
+    on_block: Option<BlockCallback>,
+    /// Blok dengan indeks di bawah nilai ini sudah dipangkas: header tersisa, transaksi dibuang.
+    pruned_height: u64,
+    /// Saldo hasil seluruh blok yang dipangkas, sebagai titik awal perhitungan saldo.
+    pruned_balances: HashMap<String, i64>,
+    /// Id transaksi non-coinbase dari blok yang dipangkas, agar tetap dikenali sebagai terkonfirmasi.
+    pruned_tx_ids: HashSet<String>,
+    /// Posisi blok berdasarkan hash, diperbarui setiap kali `blocks` diganti atau diperpanjang.
+    block_index: HashMap<String, usize>,
+    /// Id setiap transaksi di body blok yang masih utuh, diperbarui bersama `block_index`, agar
+    /// `is_confirmed` tidak perlu memindai seluruh rantai untuk setiap transaksi yang divalidasi.
+    confirmed_tx_ids: HashSet<String>,
+    /// Hasil `next_difficulty` terakhir beserta tinggi dan hash tip saat dihitung.
+    difficulty_cache: Mutex<Option<(usize, String, u32)>>,
+    /// Blok di luar rantai aktif, urut kedatangan: orphan yang induknya belum diterima, cabang fork,
+    /// dan blok stale yang ditinggalkan saat reorg.
+    orphans: Vec<OrphanBlock>,
+    /// Template yang mining-nya terputus, dengan `nonce` berisi checkpoint untuk dilanjutkan.
+    interrupted_template: Option<Block>,
+    /// Pembagian hadiah blok yang ditambang node ini; kosong berarti seluruhnya untuk `miner_address`.
+    coinbase_recipients: Vec<(String, u32)>,
+    /// Sumber body untuk blok yang dipangkas, hanya ada pada rantai hasil `warm_start`.
+    body_store: Option<BodyStore>,
+}
+
+/// Body blok di disk (`<dir>/<indeks>.json`) yang dimuat saat pertama kali diminta. Body yang
+/// sudah dibaca disimpan di cache agar setiap body hanya dimuat sekali.
+#[derive(Debug)]
+struct BodyStore {
+    dir: PathBuf,
+    cache: Mutex<HashMap<u64, Vec<Transaction>>>,
+}
+
+impl Blockchain {
+    /// Membuat blockchain baru dengan parameter konsensus tertentu lalu menambang genesis-nya.
+    pub fn new(miner_address: String, params: ConsensusParams) -> Result<Self, ConfigError> {
+        Self::new_with_allocations(miner_address, Vec::new(), params)
+    }
+
+    /// Seperti `new`, tetapi genesis juga memuat transaksi coinbase yang memberi saldo awal ke
+    /// setiap `(alamat, jumlah)` di `allocations`, mis. untuk tes transfer yang deterministik.
+    /// Alokasi ikut dihitung dalam `total_supply`.
+    pub fn new_with_allocations(miner_address: String, allocations: Vec<(String, u64)>, params: ConsensusParams) -> Result<Self, ConfigError> {
+        params.validate()?;
+        let mut chain = Self {
+            blocks: Vec::new(),
+            pending_transactions: Vec::new(),
+            miner_address,
+            total_supply: 0,
+            params,
+            mempool_subscribers: Vec::new(),
+            last_mining_duration: None,
+            on_block: None,
+            pruned_height: 0,
+            pruned_balances: HashMap::new(),
+            pruned_tx_ids: HashSet::new(),
+            block_index: HashMap::new(),
+            confirmed_tx_ids: HashSet::new(),
+            difficulty_cache: Mutex::new(None),
+            orphans: Vec::new(),
+            interrupted_template: None,
+            coinbase_recipients: Vec::new(),
+            body_store: None,
+        };
+        let total_supply = allocations
+            .iter()
+            .try_fold(chain.get_reward(0), |supply, (_, amount)| supply.checked_add(*amount))
+            .ok_or(ConfigError::AllocationOverflow)?;
+        let mut genesis_block = chain.create_genesis_block(&allocations);
+        mine_block(&mut genesis_block, chain.params.initial_difficulty, 0, interrupt_flag(), None).expect("Gagal menambang blok genesis");
+        chain.total_supply = total_supply;
+        chain.blocks.push(Arc::new(genesis_block));
+        chain.rebuild_block_index();
+        Ok(chain)
+    }
+
+    /// Membuat blockchain dari genesis yang sudah ditambang di tempat lain, agar beberapa node
+    /// berbagi titik awal yang sama. Genesis harus berindeks 0, menunjuk ke hash nol, membayar
+    /// hadiah sesuai `params`, dan memenuhi PoW-nya. Penerima coinbase genesis menjadi alamat miner.
+    pub fn with_genesis(genesis: Block, params: ConsensusParams) -> Result<Self, ChainError> {
+        params.validate()?;
+        let mut chain = Self {
+            blocks: Vec::new(),
+            pending_transactions: Vec::new(),
+            miner_address: String::new(),
+            total_supply: 0,
+            params,
+            mempool_subscribers: Vec::new(),
+            last_mining_duration: None,
+            on_block: None,
+            pruned_height: 0,
+            pruned_balances: HashMap::new(),
+            pruned_tx_ids: HashSet::new(),
+            block_index: HashMap::new(),
+            confirmed_tx_ids: HashSet::new(),
+            difficulty_cache: Mutex::new(None),
+            orphans: Vec::new(),
+            interrupted_template: None,
+            coinbase_recipients: Vec::new(),
+            body_store: None,
+        };
+        chain.verify_genesis(&genesis)?;
+        chain.miner_address = genesis.transactions[0].to.clone();
+        chain.total_supply = genesis.minted_amount();
+        chain.blocks.push(Arc::new(genesis));
+        chain.rebuild_block_index();
+        chain.is_valid()?;
+        Ok(chain)
+    }
+
+    /// Mengganti genesis dengan genesis baru dari `params`, hanya untuk rantai yang belum punya blok lain.
+    /// Semua state turunan (supply, mempool, statistik mining) direset; rantai yang sudah berisi blok
+    /// non-genesis ditolak dengan `ChainError::ChainNotEmpty` agar tidak terhapus tanpa sengaja.
+    pub fn replace_genesis(&mut self, params: ConsensusParams) -> Result<(), ChainError> {
+        if self.blocks.len() > 1 {
+            return Err(ChainError::ChainNotEmpty { height: self.blocks.len() as u64 - 1 });
+        }
+        params.validate()?;
+
+        let previous = std::mem::replace(&mut self.params, params);
+        let mut genesis_block = self.create_genesis_block(&[]);
+        if let Err(err) = mine_block(&mut genesis_block, self.params.initial_difficulty, 0, interrupt_flag(), None) {
+            self.params = previous;
+            return Err(err.into());
+        }
+
+        self.blocks = vec![Arc::new(genesis_block)];
+        self.rebuild_block_index();
+        self.total_supply = self.compute_supply();
+        self.last_mining_duration = None;
+        self.pruned_height = 0;
+        self.pruned_balances.clear();
+        self.pruned_tx_ids.clear();
+        self.body_store = None;
+        for tx in std::mem::take(&mut self.pending_transactions) {
+            self.emit_mempool_event(MempoolEvent::Removed(tx.id, RemovalReason::Invalid));
+        }
+        Ok(())
+    }
+
+    /// Menyimpan rantai (blok, alamat miner, parameter, dan mempool) ke berkas JSON.
+    pub fn save_to_file(&self, path: &Path) -> io::Result<()> {
+        let json = serde_json::to_string_pretty(&self.to_chain_file()).map_err(io::Error::other)?;
+        std::fs::write(path, json)
+    }
+
+    /// Memuat rantai dari berkas hasil `save_to_file`; `total_supply` dihitung ulang dari coinbase.
+    pub fn load_from_file(path: &Path) -> Result<Self, StorageError> {
+        let contents = std::fs::read_to_string(path).map_err(StorageError::Io)?;
+        let file: ChainFile = serde_json::from_str(&contents).map_err(StorageError::Json)?;
+        Self::from_chain_file(file)
+    }
+
+    /// Seperti `save_to_file`, tetapi dalam format biner bincode yang jauh lebih ringkas.
+    pub fn save_bincode(&self, path: &Path) -> io::Result<()> {
+        let bytes = bincode_options().serialize(&self.to_chain_file()).map_err(io::Error::other)?;
+        std::fs::write(path, bytes)
+    }
+
+    /// Memuat rantai dari berkas hasil `save_bincode`. Berkas terpotong atau rusak menghasilkan
+    /// `StorageError::Bincode`; panjang yang di-decode dibatasi ukuran berkas agar tidak mengalokasikan berlebihan.
+    pub fn load_bincode(path: &Path) -> Result<Self, StorageError> {
+        let bytes = std::fs::read(path).map_err(StorageError::Io)?;
+        let file: ChainFile = bincode_options()
+            .with_limit(bytes.len() as u64)
+            .deserialize(&bytes)
+            .map_err(StorageError::Bincode)?;
+        Self::from_chain_file(file)
+    }
+
+    /// Menyimpan isi mempool ke berkas JSON agar transaksi pending tidak hilang saat restart.
+    pub fn save_mempool(&self, path: &Path) -> io::Result<()> {
+        let json = serde_json::to_string_pretty(&self.pending_transactions).map_err(io::Error::other)?;
+        std::fs::write(path, json)
+    }
+
+    /// Memuat transaksi hasil `save_mempool` melalui `add_transaction`, sehingga transaksi yang
+    /// sudah terkonfirmasi atau tidak lagi valid dibuang alih-alih dipulihkan.
+    pub fn load_mempool(&mut self, path: &Path) -> Result<MempoolSyncReport, StorageError> {
+        let contents = std::fs::read_to_string(path).map_err(StorageError::Io)?;
+        let txs: Vec<Transaction> = serde_json::from_str(&contents).map_err(StorageError::Json)?;
+        Ok(self.apply_external_mempool(txs))
+    }
+
+    /// Menyimpan rantai ke direktori `dir` untuk `warm_start`: body setiap blok ke `bodies/<indeks>.json`,
+    /// dan `chain.json` berisi rantai yang dipangkas sedalam mungkin lewat `prune_below`, sehingga
+    /// saldo dan id transaksinya tersedia tanpa membaca body.
+    pub fn save_warm(&self, dir: &Path) -> Result<(), StorageError> {
+        let bodies = dir.join("bodies");
+        std::fs::create_dir_all(&bodies).map_err(StorageError::Io)?;
+        for index in 0..self.blocks.len() as u64 {
+            let body = match self.fetch_body(index) {
+                Ok(body) => body,
+                Err(StorageError::BodyUnavailable { .. }) => continue,
+                Err(err) => return Err(err),
+            };
+            let json = serde_json::to_string(&body).map_err(StorageError::Json)?;
+            std::fs::write(bodies.join(format!("{}.json", index)), json).map_err(StorageError::Io)?;
+        }
+        let mut headers = Self::from_chain_file(self.to_chain_file())?;
+        headers.prune_below(u64::MAX);
+        headers.save_to_file(&dir.join("chain.json")).map_err(StorageError::Io)
+    }
+
+    /// Memulai dari direktori hasil `save_warm` tanpa membaca body blok yang dipangkas. Header
+    /// langsung divalidasi (PoW, linkage, jadwal difficulty) dan saldo diambil dari snapshot;
+    /// body dimuat lewat `fetch_body` saat diminta, dan `validate_chain(ValidationLevel::Full)`
+    /// memuat semuanya untuk me-replay saldo dari genesis.
+    pub fn warm_start(dir: &Path) -> Result<Self, StorageError> {
+        let mut chain = Self::load_from_file(&dir.join("chain.json"))?;
+        chain.validate_chain(ValidationLevel::HeadersOnly).map_err(StorageError::Invalid)?;
+        chain.body_store = Some(BodyStore { dir: dir.join("bodies"), cache: Mutex::new(HashMap::new()) });
+        Ok(chain)
+    }
+
+    /// Transaksi blok di `index`. Body yang dipangkas dimuat dari penyimpanan `warm_start` saat
+    /// pertama diminta dan dicocokkan dengan merkle root header sebelum disimpan di cache.
+    pub fn fetch_body(&self, index: u64) -> Result<Vec<Transaction>, StorageError> {
+        let block = self.get_block_by_index(index).ok_or(StorageError::BodyUnavailable { index })?;
+        if index >= self.pruned_height {
+            return Ok(block.transactions.clone());
+        }
+        let store = self.body_store.as_ref().ok_or(StorageError::BodyUnavailable { index })?;
+        let mut cache = store.cache.lock().unwrap();
+        if let Some(body) = cache.get(&index) {
+            return Ok(body.clone());
+        }
+        let contents = match std::fs::read_to_string(store.dir.join(format!("{}.json", index))) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Err(StorageError::BodyUnavailable { index }),
+            Err(err) => return Err(StorageError::Io(err)),
+        };
+        let body: Vec<Transaction> = serde_json::from_str(&contents).map_err(StorageError::Json)?;
+        if MerkleTree::new(&body).build_tree() != block.merkle_root {
+            return Err(StorageError::BodyUnavailable { index });
+        }
+        cache.insert(index, body.clone());
+        Ok(body)
+    }
+
+    fn to_chain_file(&self) -> ChainFile {
+        ChainFile {
+            miner_address: self.miner_address.clone(),
+            params: self.params.clone(),
+            blocks: self.blocks.clone(),
+            pending_transactions: self.pending_transactions.clone(),
+            pruned_height: self.pruned_height,
+            pruned_balances: self.pruned_balances.clone(),
+            pruned_tx_ids: self.pruned_tx_ids.clone(),
+        }
+    }
+
+    fn from_chain_file(file: ChainFile) -> Result<Self, StorageError> {
+        file.params.validate().map_err(StorageError::Config)?;
+        if file.blocks.is_empty() {
+            return Err(StorageError::EmptyChain);
+        }
+
+        // Coin dari blok yang dipangkas hanya tersisa sebagai saldo; jumlahnya sama dengan yang dicetak.
+        let pruned_supply: i64 = file.pruned_balances.values().sum();
+        let mut total_supply = u64::try_from(pruned_supply).map_err(|_| StorageError::SupplyOverflow)?;
+        for block in &file.blocks {
+            total_supply = total_supply.checked_add(block.minted_amount()).ok_or(StorageError::SupplyOverflow)?;
+        }
+
+        let mut chain = Self {
+            blocks: file.blocks,
+            pending_transactions: file.pending_transactions,
+            miner_address: file.miner_address,
+            total_supply,
+            params: file.params,
+            mempool_subscribers: Vec::new(),
+            last_mining_duration: None,
+            on_block: None,
+            pruned_height: file.pruned_height,
+            pruned_balances: file.pruned_balances,
+            pruned_tx_ids: file.pruned_tx_ids,
+            block_index: HashMap::new(),
+            confirmed_tx_ids: HashSet::new(),
+            difficulty_cache: Mutex::new(None),
+            orphans: Vec::new(),
+            interrupted_template: None,
+            coinbase_recipients: Vec::new(),
+            body_store: None,
+        };
+        chain.rebuild_block_index();
+        Ok(chain)
+    }
+
+    /// Memasang callback yang dipanggil setelah setiap blok baru masuk lewat `submit_block`
+    /// (termasuk dari `mine_and_add_block`), sesudah blok ditambahkan dan `total_supply` diperbarui.
+    /// Callback sebelumnya, jika ada, diganti.
+    pub fn set_on_block(&mut self, f: Box<dyn Fn(&Block) + Send + Sync>) {
+        self.on_block = Some(BlockCallback(f));
+    }
+
+    /// Mengembalikan parameter konsensus yang digunakan rantai ini.
+    pub fn params(&self) -> &ConsensusParams {
+        &self.params
+    }
+
+    /// Membuat blok genesis: hadiah miner lebih dulu, lalu satu coinbase per alokasi awal.
+    fn create_genesis_block(&self, allocations: &[(String, u64)]) -> Block {
+        let reward = self.get_reward(0);
+        let mut transactions = vec![Transaction::coinbase(self.miner_address.clone(), reward)];
+        transactions.extend(allocations.iter().map(|(address, amount)| Transaction::coinbase(address.clone(), *amount)));
+        Block::new(0, "0".repeat(64), self.params.initial_difficulty, transactions)
+    }
+
+    /// Memeriksa blok genesis dari luar: struktur dasar dan hadiah coinbase harus sesuai konfigurasi,
+    /// agar genesis palsu tidak bisa mencetak koin tambahan.
+    pub fn verify_genesis(&self, genesis: &Block) -> Result<(), ChainError> {
+        if genesis.index != 0 || genesis.previous_hash != "0".repeat(64) {
+            return Err(ChainError::InvalidGenesis);
+        }
+        let coinbase = genesis.transactions.first().filter(|tx| tx.is_coinbase()).ok_or(ChainError::InvalidGenesis)?;
+        let expected = self.get_reward(0);
+        if coinbase.total_output_amount() != expected {
+            return Err(ChainError::GenesisRewardMismatch { expected, found: coinbase.total_output_amount() });
+        }
+        Ok(())
+    }
+
+    /// Menghitung hadiah mining berdasarkan tinggi blok.
+    pub fn get_reward(&self, block_index: u64) -> u64 {
+        let halvings = block_index / self.params.halving_interval;
+        // Setelah 64 kali halving, pergeseran bit akan overflow dan hadiah sudah habis.
+        if halvings >= 64 {
+            return 0;
+        }
+        self.params.initial_reward >> halvings
+    }
+
+    /// Tabel hadiah per rentang tinggi blok hingga `up_to_height` (eksklusif).
+    /// Begitu hadiah mencapai 0, satu baris terakhir mencakup sisa rentangnya.
+    pub fn reward_schedule_table(&self, up_to_height: u64) -> Vec<(Range<u64>, u64)> {
+        let interval = self.params.halving_interval;
+        let mut table = Vec::new();
+        let mut start = 0;
+        while start < up_to_height {
+            let reward = self.get_reward(start);
+            let end = if reward == 0 { up_to_height } else { start.saturating_add(interval).min(up_to_height) };
+            table.push((start..end, reward));
+            start = end;
+        }
+        table
+    }
+
+    /// Titik perubahan hadiah sampai `up_to_height` (inklusif) sebagai pasangan `(tinggi, hadiah)`:
+    /// baris pertama tinggi 0, lalu satu baris di setiap halving hingga hadiah menjadi 0.
+    pub fn emission_schedule(&self, up_to_height: u64) -> Vec<(u64, u64)> {
+        self.reward_schedule_table(up_to_height.saturating_add(1))
+            .into_iter()
+            .map(|(range, reward)| (range.start, reward))
+            .collect()
+    }
+
+    /// Jumlah blok lagi sampai halving berikutnya, dihitung dari tinggi rantai saat ini.
+    pub fn blocks_until_next_halving(&self) -> u64 {
+        let interval = self.params.halving_interval;
+        interval - (self.blocks.len() as u64 % interval)
+    }
+
+    /// Tinggi blok tempat halving berikutnya terjadi.
+    pub fn next_halving_height(&self) -> u64 {
+        self.blocks.len() as u64 + self.blocks_until_next_halving()
+    }
+
+    /// Menghitung nilai coinbase (hadiah + fee) tanpa wrapping saat overflow.
+    pub fn coinbase_amount(reward: u64, total_fees: u64) -> Result<u64, ChainError> {
+        reward.checked_add(total_fees).ok_or(ChainError::SupplyOverflow)
+    }
+
+    /// Menyesuaikan kesulitan mining.
+    pub fn adjust_difficulty(&self) -> Result<u32, ChainError> {
+        self.ensure_consistent_tip()?;
+        Ok(self.expected_difficulty_at(self.blocks.len()))
+    }
+
+    /// Kesulitan blok berikutnya seperti `adjust_difficulty`, tetapi di-cache sampai tip berubah
+    /// sehingga tampilan CLI dan template mining memakai hasil perhitungan yang sama.
+    pub fn next_difficulty(&self) -> Result<u32, ChainError> {
+        self.ensure_consistent_tip()?;
+        let tip_hash = &self.blocks.last().unwrap().hash;
+        let mut cache = self.difficulty_cache.lock().expect("Lock cache difficulty teracuni");
+        if let Some((height, cached_tip, difficulty)) = cache.as_ref() {
+            if *height == self.blocks.len() && cached_tip == tip_hash {
+                return Ok(*difficulty);
+            }
+        }
+        let difficulty = self.expected_difficulty_at(self.blocks.len());
+        *cache = Some((self.blocks.len(), tip_hash.clone(), difficulty));
+        Ok(difficulty)
+    }
+
+    /// Memastikan tip sesuai dengan tinggi rantai sebelum dipakai sebagai input retarget,
+    /// mis. agar state setengah jadi di tengah reorg tidak diam-diam menghasilkan difficulty salah.
+    fn ensure_consistent_tip(&self) -> Result<(), ChainError> {
+        let height = self.blocks.len() as u64;
+        match self.blocks.last() {
+            Some(tip) if tip.index + 1 == height => Ok(()),
+            tip => Err(ChainError::InconsistentTip { height, tip_index: tip.map_or(0, |tip| tip.index) }),
+        }
+    }
+
+    /// Menghitung kesulitan yang seharusnya dimiliki blok pada tinggi `height`,
+    /// hanya berdasarkan blok-blok sebelumnya.
+    fn expected_difficulty_at(&self, height: usize) -> u32 {
+        if height == 0 {
+            return self.params.initial_difficulty;
+        }
+        let history = &self.blocks[..height];
+        let interval = self.params.difficulty_adjustment_interval;
+        if history.len() < interval as usize {
+            return history.last().unwrap().difficulty;
+        }
+        
+        let last_adjustment_block = &history[history.len() - interval as usize];
+        let current_block = history.last().unwrap();
+        
+        // Minimal 1ms agar dua blok dengan timestamp sama tidak menyebabkan pembagian dengan nol.
+        let time_taken = current_block.timestamp.saturating_sub(last_adjustment_block.timestamp).max(1);
+        let expected_time = interval * self.params.block_time_seconds * 1000;
+
+        let time_ratio = expected_time as f64 / time_taken as f64;
+        let old_difficulty = current_block.difficulty as f64;
+
+        // Batasi perubahan difficulty (misal, max 4x)
+        let mut new_difficulty = if time_ratio > 4.0 {
+            old_difficulty * 4.0
+        } else if time_ratio < 0.25 {
+            old_difficulty * 0.25
+        } else {
+            old_difficulty * time_ratio
+        };
+
+        // Batasi perubahan kumulatif terhadap difficulty di awal jendela rolling
+        if let Some(limit) = &self.params.difficulty_rate_limit {
+            let window_start = height.saturating_sub((limit.window_retargets * interval) as usize);
+            let reference = history[window_start].difficulty as f64;
+            new_difficulty = new_difficulty.clamp(reference / limit.max_factor, reference * limit.max_factor);
+        }
+        
+        // Batasi ke rentang konsensus; `as u32` menjenuhkan nilai tak hingga maupun NaN.
+        (new_difficulty.round() as u32).clamp(self.params.min_difficulty, self.params.max_difficulty)
+    }
+
+    /// Mendaftar blok yang `difficulty`-nya tidak sesuai hasil retarget sebagai
+    /// `(tinggi, tersimpan, seharusnya)`. Hanya diagnosa: mengubah difficulty akan mengubah hash.
+    pub fn audit_difficulty(&self) -> Vec<(u64, u32, u32)> {
+        (0..self.blocks.len())
+            .filter_map(|height| {
+                let stored = self.blocks[height].difficulty;
+                let expected = self.expected_difficulty_at(height);
+                (stored != expected).then_some((height as u64, stored, expected))
+            })
+            .collect()
+    }
+
+    /// Memeriksa konsistensi internal rantai: hash, linkage (genesis menunjuk ke hash nol),
+    /// proof of work, merkle root, dan `Transaction::validate` untuk setiap transaksi di setiap blok.
+    /// Saldo dan jadwal difficulty tidak diperiksa.
+    /// Blok yang sudah dipangkas hanya diperiksa header-nya.
+    pub fn is_valid(&self) -> Result<(), ValidationError> {
+        self.validate_range(0, self.blocks.len() as u64)
+    }
+
+    /// Seperti `is_valid`, tetapi hanya untuk blok `start..end` (eksklusif), mis. saat sinkronisasi
+    /// bertahap dari peer. Blok `start` harus tertaut ke blok `start - 1` yang sudah ada di rantai.
+    pub fn validate_range(&self, start: u64, end: u64) -> Result<(), ValidationError> {
+        if start > end || end > self.blocks.len() as u64 {
+            return Err(ValidationError::RangeOutOfBounds { start, end });
+        }
+        for height in start as usize..end as usize {
+            self.check_header(height)?;
+            if (height as u64) < self.pruned_height {
+                continue;
+            }
+            if MerkleTree::new(&self.blocks[height].transactions).build_tree() != self.blocks[height].merkle_root {
+                return Err(ValidationError::MerkleMismatch { index: height as u64 });
+            }
+            if let Some(error) = self.blocks[height].transactions.iter().find_map(|tx| tx.validate().err()) {
+                return Err(ValidationError::InvalidTransaction { index: height as u64, error });
+            }
+        }
+        Ok(())
+    }
+
+    /// Pemeriksaan yang hanya memakai data header: indeks, linkage, hash, dan PoW.
+    fn check_header(&self, height: usize) -> Result<(), ValidationError> {
+        let block = &self.blocks[height];
+        let index = height as u64;
+        if block.index != index {
+            return Err(ValidationError::IndexMismatch { index, found: block.index });
+        }
+        let expected_previous = match height {
+            0 => "0".repeat(64),
+            _ => self.blocks[height - 1].hash.clone(),
+        };
+        if block.previous_hash != expected_previous {
+            return Err(ValidationError::BrokenLink { index });
+        }
+        if height > 0 && block.timestamp < self.blocks[height - 1].timestamp {
+            return Err(ValidationError::TimestampRegression { index });
+        }
+        // Hash dihitung dari header agar body yang dipangkas tetap bisa diperiksa.
+        if block.hash != block.header().calculate_hash() {
+            return Err(ValidationError::HashMismatch { index });
+        }
+        if !hash_meets_difficulty(&block.hash, block.difficulty) {
+            return Err(ValidationError::BadProofOfWork { index });
+        }
+        Ok(())
+    }
+
+    /// Memvalidasi seluruh rantai sesuai `level`; error pertama yang ditemukan dikembalikan.
+    pub fn validate_chain(&self, level: ValidationLevel) -> Result<(), ChainError> {
+        // Rantai hasil `warm_start` masih punya body yang dipangkas di disk: validasi penuh memuatnya,
+        // me-replay saldo dari genesis, lalu mencocokkan hasilnya dengan snapshot `pruned_balances`.
+        let replay_pruned = level == ValidationLevel::Full && self.body_store.is_some();
+        let mut balances = if replay_pruned { HashMap::new() } else { self.pruned_balances.clone() };
+        for (height, block) in self.blocks.iter().enumerate() {
+            let index = height as u64;
+            self.check_header(height)?;
+            if level == ValidationLevel::PowAndLinkage {
+                continue;
+            }
+
+            let expected = self.expected_difficulty_at(height);
+            if block.difficulty != expected {
+                return Err(ValidationError::UnexpectedDifficulty { index, expected, found: block.difficulty }.into());
+            }
+            if level == ValidationLevel::HeadersOnly {
+                continue;
+            }
+            let fetched;
+            let block = if index < self.pruned_height {
+                // Body blok yang dipangkas tidak ada lagi; saldonya sudah terwakili `pruned_balances`.
+                if !replay_pruned {
+                    continue;
+                }
+                let transactions = self.fetch_body(index).map_err(|_| ValidationError::MissingBody { index })?;
+                fetched = Block { transactions, ..Block::clone(block) };
+                &fetched
+            } else {
+                if replay_pruned && index == self.pruned_height && !same_nonzero_balances(&balances, &self.pruned_balances) {
+                    return Err(ValidationError::SnapshotMismatch { index }.into());
+                }
+                block.as_ref()
+            };
+
+            if MerkleTree::new(&block.transactions).build_tree() != block.merkle_root {
+                return Err(ValidationError::MerkleMismatch { index }.into());
+            }
+            if let Some(error) = block.transactions.iter().find_map(|tx| tx.validate().err()) {
+                return Err(ValidationError::InvalidTransaction { index, error }.into());
+            }
+            if height == 0 {
+                self.verify_genesis(block)?;
+            }
+            balances = block.validate_against_balances(&balances)
+                .map_err(|error| ValidationError::InvalidTransaction { index, error })?;
+        }
+        Ok(())
+    }
+
+    /// Menambang blok baru dan menambahkannya ke rantai. Transaksi baru keluar dari mempool
+    /// setelah blok berhasil ditambang, sehingga interupsi tidak menghilangkannya. Template yang
+    /// terputus disimpan dan dilanjutkan dari checkpoint nonce-nya pada pemanggilan berikutnya.
+    pub fn mine_and_add_block(&mut self) -> Result<(), ChainError> {
+        let mut new_block = self.resumable_template()?;
+        let (difficulty, start_nonce) = (new_block.difficulty, new_block.nonce);
+
+        let stats = match mine_block(&mut new_block, difficulty, start_nonce, interrupt_flag(), None) {
+            Ok(stats) => stats,
+            Err(err) => {
+                if matches!(err, MiningError::Interrupted) {
+                    println!("
+Mining blok #{} dihentikan; nonce di bawah {} sudah diperiksa.", new_block.index, new_block.nonce);
+                    self.interrupted_template = Some(new_block);
+                }
+                return Err(err.into());
+            }
+        };
+        self.last_mining_duration = Some(stats.elapsed);
+        
+        println!("
+Blok #{} berhasil ditambang!", new_block.index);
+        println!("  Hash: {}", new_block.hash);
+        println!("  Nonce: {} ({} percobaan, {})", stats.nonce, stats.attempts, hashrate_as_human(stats.hashes_per_second));
+        println!("  Kesulitan: {}", new_block.difficulty);
+        println!("  Hadiah: {}", new_block.transactions[0].amount);
+
+        self.submit_block(new_block)
+    }
+
+    /// Template yang mining-nya terputus, jika ada; `nonce`-nya adalah checkpoint untuk dilanjutkan.
+    pub fn interrupted_template(&self) -> Option<&Block> {
+        self.interrupted_template.as_ref()
+    }
+
+    /// Mengambil template yang terputus selama tip belum berubah, atau menyusun template baru.
+    /// Transaksi yang masuk mempool setelah interupsi menunggu blok berikutnya.
+    fn resumable_template(&mut self) -> Result<Block, ChainError> {
+        let tip = &self.blocks.last().unwrap().hash;
+        match self.interrupted_template.take() {
+            Some(block) if &block.previous_hash == tip => Ok(block),
+            _ => self.block_template(),
+        }
+    }
+
+    /// Membagi hadiah blok yang ditambang node ini (hadiah + fee) ke beberapa alamat sesuai bobotnya,
+    /// mis. `[("pool", 70), ("operator", 30)]`; sisa pembulatan jatuh ke penerima pertama. Daftar
+    /// kosong mengembalikan seluruh hadiah ke `miner_address`. Bobot total 0 atau alamat kosong ditolak.
+    pub fn set_coinbase_recipients(&mut self, recipients: Vec<(String, u32)>) -> Result<(), ConfigError> {
+        let total_weight: u64 = recipients.iter().map(|(_, weight)| u64::from(*weight)).sum();
+        if !recipients.is_empty() && (total_weight == 0 || recipients.iter().any(|(address, _)| address.is_empty())) {
+            return Err(ConfigError::InvalidCoinbaseRecipients);
+        }
+        self.coinbase_recipients = recipients;
+        self.interrupted_template = None;
+        Ok(())
+    }
+
+    /// Menyusun kandidat blok berikutnya (coinbase + transaksi pilihan dari mempool) tanpa mengubah rantai.
+    /// Nonce belum dicari; pemanggil menambang template lalu mengirimkannya lewat `submit_block`.
+    pub fn block_template(&self) -> Result<Block, ChainError> {
+        let selected = self.select_transactions();
+        let reward = self.get_reward(self.blocks.len() as u64);
+        let fees = selected.iter().fold(0u64, |sum, tx| sum.saturating_add(tx.fee));
+        let coinbase_amount = Self::coinbase_amount(reward, fees)?;
+        self.total_supply.checked_add(reward).ok_or(ChainError::SupplyOverflow)?;
+        let difficulty = self.next_difficulty()?;
+        let coinbase = match self.coinbase_recipients.as_slice() {
+            [] => Transaction::coinbase(self.miner_address.clone(), coinbase_amount),
+            recipients => Transaction::split_coinbase(recipients, coinbase_amount),
+        };
+        let mut transactions = vec![coinbase];
+        transactions.extend(selected);
+        Ok(Block::new(
+            self.blocks.len() as u64,
+            self.blocks.last().unwrap().hash.clone(),
+            difficulty,
+            transactions
+        ))
+    }
+
+    /// Memilih transaksi mempool untuk blok berikutnya, fee tertinggi lebih dulu, paling banyak
+    /// `max_transactions_per_block`. Transaksi yang bergantung pada transaksi lain (mis. membelanjakan
+    /// koin yang baru diterima) menunggu sampai induknya terpilih, sehingga urutan di blok selalu lolos
+    /// `validate_against_balances`. Sisanya tetap di mempool untuk blok berikutnya.
+    ///
+    /// Setiap langkah memilih transaksi ber-fee tertinggi yang saldonya cukup. Transaksi yang belum
+    /// cukup hanya diperiksa ulang setelah pengirimnya menerima kredit, jadi mempool tidak dipindai
+    /// ulang untuk setiap transaksi yang terpilih.
+    fn select_transactions(&self) -> Vec<Transaction> {
+        let candidates: Vec<&Transaction> = self.pending_transactions.iter().collect();
+        Self::fee_order(&candidates, self.spendable_balances(), self.params.max_transactions_per_block)
+            .into_iter()
+            .map(|position| candidates[position].clone())
+            .collect()
+    }
+
+    /// Urutan `select_transactions` atas `transactions` terhadap `balances`: posisi transaksi yang
+    /// terpilih, paling banyak `limit`. Fee yang sama tetap sesuai urutan di `transactions`.
+    fn fee_order(transactions: &[&Transaction], mut balances: HashMap<String, i64>, limit: usize) -> Vec<usize> {
+        let mut candidates: Vec<usize> = (0..transactions.len()).collect();
+        candidates.sort_by_key(|&position| std::cmp::Reverse(transactions[position].fee));
+
+        let mut selected = Vec::new();
+        // Posisi yang saldonya belum cukup, per pengirim, dan posisi yang perlu diperiksa ulang.
+        let mut waiting: HashMap<&str, Vec<usize>> = HashMap::new();
+        let mut recheck = BinaryHeap::new();
+        let mut cursor = 0;
+        while selected.len() < limit {
+            let position = match recheck.peek() {
+                Some(&std::cmp::Reverse(position)) if position < cursor => {
+                    recheck.pop();
+                    position
+                }
+                _ if cursor < candidates.len() => {
+                    cursor += 1;
+                    cursor - 1
+                }
+                _ => break,
+            };
+            let tx = transactions[candidates[position]];
+            // `apply_to_balances` hanya mendebit saldo jika `balance >= amount + fee`.
+            if Self::apply_to_balances(&mut balances, tx).is_ok() {
+                selected.push(candidates[position]);
+                recheck.extend(waiting.remove(tx.to.as_str()).into_iter().flatten().map(std::cmp::Reverse));
+            } else {
+                waiting.entry(tx.from.as_str()).or_default().push(position);
+            }
+        }
+        selected
+    }
+
+    /// Waktu nyata (bukan selisih timestamp blok) yang dihabiskan untuk menambang blok terakhir.
+    /// `None` jika belum ada blok yang ditambang oleh instance ini.
+    pub fn time_to_mine_last_block(&self) -> Option<Duration> {
+        self.last_mining_duration
+    }
+
+    /// Rata-rata selang antar blok dalam detik, dihitung dari timestamp blok (bukan waktu mining lokal).
+    /// `None` jika rantai baru berisi genesis.
+    pub fn average_block_time(&self) -> Option<f64> {
+        let (first, last) = (self.blocks.first()?, self.blocks.last()?);
+        let intervals = self.blocks.len().checked_sub(1).filter(|&n| n > 0)?;
+        Some(last.timestamp.saturating_sub(first.timestamp) as f64 / 1000.0 / intervals as f64)
+    }
+
+    /// Menambang blok terus-menerus sampai `predicate` terpenuhi; mengembalikan jumlah blok yang ditambang.
+    /// Interupsi Ctrl-C dihentikan sebagai `ChainError::Mining(MiningError::Interrupted)`.
+    pub fn mine_until(&mut self, predicate: impl Fn(&Blockchain) -> bool) -> Result<u64, ChainError> {
+        let mut mined = 0;
+        while !predicate(self) {
+            self.mine_and_add_block()?;
+            mined += 1;
+        }
+        Ok(mined)
+    }
+
+    /// Memvalidasi transaksi terhadap saldo lalu memasukkannya ke mempool.
+    /// Transaksi yang id-nya sudah ada di mempool atau di blok ditolak sebagai replay.
+    /// Jika mempool penuh, transaksi dengan fee terendah dikeluarkan untuk memberi tempat, atau
+    /// transaksi baru ditolak dengan `TxError::MempoolFull` bila fee-nya tidak lebih tinggi.
+    pub fn add_transaction(&mut self, tx: Transaction) -> Result<(), TxError> {
+        tx.validate()?;
+        if self.contains_transaction(&tx.id) {
+            return Err(TxError::Duplicate);
+        }
+        if !tx.verify_signature() {
+            return Err(TxError::InvalidSignature);
+        }
+        let mut balances = self.provisional_balances();
+        Self::apply_to_balances(&mut balances, &tx)?;
+        if self.pending_transactions.len() >= self.params.max_mempool_size {
+            self.evict_lowest_fee(tx.fee)?;
+            // Transaksi yang dikeluarkan bisa saja kredit yang dibutuhkan transaksi baru ini.
+            Self::apply_to_balances(&mut self.provisional_balances(), &tx)?;
+        }
+        let id = tx.id.clone();
+        self.pending_transactions.push(tx);
+        self.emit_mempool_event(MempoolEvent::Added(id));
+        Ok(())
+    }
+
+    /// Mengeluarkan transaksi mempool dengan fee terendah (yang tertua jika sama) bila fee-nya di
+    /// bawah `incoming_fee`, lalu menjalankan `compact_mempool` agar transaksi yang membelanjakan
+    /// kreditnya ikut keluar alih-alih tertahan di mempool tanpa pernah bisa ditambang.
+    fn evict_lowest_fee(&mut self, incoming_fee: u64) -> Result<(), TxError> {
+        let (position, min_fee) = self.pending_transactions
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, tx)| tx.fee)
+            .map(|(position, tx)| (position, tx.fee))
+            .ok_or(TxError::MempoolFull { min_fee: 0 })?;
+        if incoming_fee <= min_fee {
+            return Err(TxError::MempoolFull { min_fee });
+        }
+        let evicted = self.pending_transactions.remove(position);
+        self.emit_mempool_event(MempoolEvent::Removed(evicted.id, RemovalReason::Evicted));
+        self.compact_mempool();
+        Ok(())
+    }
+
+    /// Jumlah transaksi yang sedang menunggu di mempool.
+    pub fn mempool_size(&self) -> usize {
+        self.pending_transactions.len()
+    }
+
+    /// Mengimpor mempool peer secara massal. Transaksi duplikat atau invalid dilewati
+    /// satu per satu tanpa menggagalkan seluruh batch.
+    pub fn apply_external_mempool(&mut self, txs: Vec<Transaction>) -> MempoolSyncReport {
+        let mut report = MempoolSyncReport::default();
+        for tx in txs {
+            if self.contains_transaction(&tx.id) {
+                report.duplicate += 1;
+            } else if self.add_transaction(tx).is_ok() {
+                report.accepted += 1;
+            } else {
+                report.rejected += 1;
+            }
+        }
+        report
+    }
+
+    /// Membersihkan mempool: membuang duplikat id, transaksi yang sudah terkonfirmasi,
+    /// dan transaksi yang tidak lagi valid terhadap saldo terkonfirmasi. Transaksi yang tersisa
+    /// diurutkan seperti `select_transactions`: fee tertinggi lebih dulu (fee sama sesuai urutan
+    /// kedatangan), tetapi transaksi yang membelanjakan kredit dari transaksi pending lain tetap di
+    /// belakang induknya, sehingga compaction berikutnya tidak membuangnya sebagai invalid.
+    pub fn compact_mempool(&mut self) -> MempoolCompaction {
+        let mut summary = MempoolCompaction::default();
+        let mut seen = HashSet::new();
+        let mut unconfirmed = Vec::new();
+        let mut removed = Vec::new();
+
+        for tx in std::mem::take(&mut self.pending_transactions) {
+            if !seen.insert(tx.id.clone()) {
+                summary.duplicates += 1;
+            } else if self.is_confirmed(&tx.id) {
+                summary.confirmed += 1;
+                removed.push((tx.id, RemovalReason::Confirmed));
+            } else {
+                unconfirmed.push(tx);
+            }
+        }
+
+        let order = Self::fee_order(&unconfirmed.iter().collect::<Vec<_>>(), self.spendable_balances(), usize::MAX);
+        let mut slots: Vec<Option<Transaction>> = unconfirmed.into_iter().map(Some).collect();
+        let kept = order.into_iter().filter_map(|position| slots[position].take()).collect();
+        for tx in slots.into_iter().flatten() {
+            summary.invalid += 1;
+            removed.push((tx.id, RemovalReason::Invalid));
+        }
+        self.pending_transactions = kept;
+        for (id, reason) in removed {
+            self.emit_mempool_event(MempoolEvent::Removed(id, reason));
+        }
+        summary
+    }
+
+    /// Mengecek apakah transaksi dengan id tertentu sudah ada di mempool atau di salah satu blok.
+    pub fn contains_transaction(&self, id: &str) -> bool {
+        self.pending_transactions.iter().any(|tx| tx.id == id) || self.is_confirmed(id)
+    }
+
+    /// Mengecek apakah transaksi dengan id tertentu sudah masuk ke salah satu blok, termasuk blok
+    /// yang body-nya sudah dipangkas.
+    fn is_confirmed(&self, id: &str) -> bool {
+        self.pruned_tx_ids.contains(id) || self.confirmed_tx_ids.contains(id)
+    }
+
+    /// Transaksi keluar dari `address` yang masih menunggu konfirmasi.
+    pub fn pending_by_sender(&self, address: &str) -> Vec<&Transaction> {
+        self.pending_transactions.iter().filter(|tx| tx.from == address).collect()
+    }
+
+    /// Berlangganan event mempool; setiap pelanggan menerima salinan setiap event.
+    pub fn subscribe_mempool(&mut self) -> Receiver<MempoolEvent> {
+        let (sender, receiver) = mpsc::channel();
+        self.mempool_subscribers.push(sender);
+        receiver
+    }
+
+    /// Mengirim event ke semua pelanggan dan membuang pelanggan yang sudah terputus.
+    fn emit_mempool_event(&mut self, event: MempoolEvent) {
+        self.mempool_subscribers.retain(|subscriber| subscriber.send(event.clone()).is_ok());
+    }
+
+    /// Memeriksa blok dari peer sebelum ditambahkan, tanpa mengubah rantai: linkage ke tip, indeks
+    /// berurutan, timestamp, hash dan PoW, difficulty sesuai retarget, merkle root, coinbase tunggal
+    /// di posisi pertama senilai hadiah ditambah fee, signature setiap transaksi non-coinbase, dan
+    /// saldo: transaksi di-replay berurutan terhadap saldo yang bisa dibelanjakan di blok induk, sehingga
+    /// beberapa transaksi yang bersama-sama melebihi saldo pengirim tertolak sebagai `DoubleSpend`.
+    pub fn verify_block(&self, block: &Block) -> Result<(), ValidationError> {
+        let tip = self.blocks.last().unwrap();
+        let index = tip.index + 1;
+        if block.previous_hash != tip.hash {
+            return Err(ValidationError::BrokenLink { index });
+        }
+        if block.index != index {
+            return Err(ValidationError::IndexMismatch { index, found: block.index });
+        }
+        if block.timestamp < tip.timestamp {
+            return Err(ValidationError::TimestampRegression { index });
+        }
+        if block.hash != block.calculate_hash() {
+            return Err(ValidationError::HashMismatch { index });
+        }
+        if !hash_meets_difficulty(&block.hash, block.difficulty) {
+            return Err(ValidationError::BadProofOfWork { index });
+        }
+        let expected = self.expected_difficulty_at(self.blocks.len());
+        if block.difficulty != expected {
+            return Err(ValidationError::UnexpectedDifficulty { index, expected, found: block.difficulty });
+        }
+        if MerkleTree::new(&block.transactions).build_tree() != block.merkle_root {
+            return Err(ValidationError::MerkleMismatch { index });
+        }
+        let limit = self.params.max_transactions_per_block;
+        let found = block.transactions.iter().filter(|tx| !tx.is_coinbase()).count();
+        if found > limit {
+            return Err(ValidationError::TooManyTransactions { index, limit, found });
+        }
+        // Id dari blok tidak dipercaya: tanpa cek ini transaksi yang sudah terkonfirmasi bisa
+        // ditambang ulang dengan id baru dan lolos dari deteksi duplikat di bawah.
+        if let Some(error) = block.transactions.iter().find_map(|tx| tx.validate().err()) {
+            return Err(ValidationError::InvalidTransaction { index, error });
+        }
+        // Coinbase dikecualikan: id-nya hanya bergantung pada penerima, hadiah, dan timestamp.
+        let mut seen = HashSet::new();
+        if let Some(tx) = block.transactions.iter().filter(|tx| !tx.is_coinbase()).find(|tx| {
+            let hash = tx.calculate_hash();
+            self.is_confirmed(&hash) || !seen.insert(hash)
+        }) {
+            return Err(ValidationError::DuplicateTransaction { index, tx_id: tx.id.clone() });
+        }
+        // Tanpa cek ini miner bisa mencetak koin sebanyak apa pun lewat coinbase-nya sendiri.
+        let expected_coinbase = self.get_reward(block.index).checked_add(block.total_fees());
+        let coinbase_valid = match block.transactions.split_first() {
+            Some((coinbase, rest)) => coinbase.is_coinbase()
+                && Some(coinbase.total_output_amount()) == expected_coinbase
+                && !rest.iter().any(Transaction::is_coinbase),
+            None => false,
+        };
+        if !coinbase_valid {
+            return Err(ValidationError::InvalidCoinbase { index });
+        }
+        if block.transactions.iter().any(|tx| !tx.verify_signature()) {
+            return Err(ValidationError::InvalidTransaction { index, error: TxError::InvalidSignature });
+        }
+        let mut balances = self.spendable_balances();
+        for (position, tx) in block.transactions.iter().enumerate() {
+            if position == 0 {
+                // Hadiah blok ini baru bisa dibelanjakan di blok yang sama jika tidak ada masa maturity.
+                if self.params.coinbase_maturity == 0 {
+                    Self::credit_outputs(&mut balances, tx);
+                }
+                continue;
+            }
+            if Self::apply_to_balances(&mut balances, tx).is_err() {
+                return Err(ValidationError::DoubleSpend { index, tx_id: tx.id.clone() });
+            }
+        }
+        Ok(())
+    }
+
+    /// Menerima blok yang ditambang di luar node dan menambahkannya jika memperpanjang tip.
+    /// Setelah pemeriksaan tip dan PoW, blok harus lolos seluruh aturan `verify_block`.
+    pub fn submit_block(&mut self, block: Block) -> Result<(), ChainError> {
+        let tip = self.blocks.last().unwrap();
+        // Blok yang tidak menunjuk ke tip adalah orphan atau cabang fork, bukan perpanjangan langsung.
+        if block.previous_hash != tip.hash {
+            return Err(ChainError::NotExtendingTip);
+        }
+        if block.index != tip.index + 1 {
+            return Err(ChainError::UnexpectedIndex { expected: tip.index + 1, found: block.index });
+        }
+        if block.timestamp < tip.timestamp {
+            return Err(ValidationError::TimestampRegression { index: block.index }.into());
+        }
+        if block.hash != block.calculate_hash() || !hash_meets_difficulty(&block.hash, block.difficulty) {
+            return Err(ChainError::InvalidProofOfWork);
+        }
+        // Aturan isi blok (difficulty, coinbase, signature, saldo) sama dengan blok dari peer.
+        self.verify_block(&block).map_err(|err| match err {
+            ValidationError::TooManyTransactions { limit, found, .. } => ChainError::TooManyTransactions { limit, found },
+            ValidationError::DuplicateTransaction { tx_id, .. } => ChainError::DuplicateTransaction(tx_id),
+            err => err.into(),
+        })?;
+
+        let new_supply = self.total_supply.checked_add(block.minted_amount()).ok_or(ChainError::SupplyOverflow)?;
+        let (confirmed, remaining): (Vec<_>, Vec<_>) = self.pending_transactions
+            .drain(..)
+            .partition(|pending| block.transactions.iter().any(|tx| tx.id == pending.id));
+        self.pending_transactions = remaining;
+        self.total_supply = new_supply;
+        self.push_block(block);
+        for tx in confirmed {
+            self.emit_mempool_event(MempoolEvent::Removed(tx.id, RemovalReason::Confirmed));
+        }
+        if let Some(BlockCallback(callback)) = &self.on_block {
+            callback(self.blocks.last().unwrap());
+        }
+        Ok(())
+    }
+
+    /// Menambahkan blok jika belum dimiliki; `Ok(false)` berarti blok duplikat dari peer lain.
+    pub fn add_block_if_new(&mut self, block: Block) -> Result<bool, ChainError> {
+        if self.contains_block(&block.hash) {
+            return Ok(false);
+        }
+        self.submit_block(block)?;
+        Ok(true)
+    }
+
+    /// Menyimpan blok yang belum bisa ditambahkan, mis. karena datang sebelum induknya, lalu
+    /// menjalankan `connect_orphans`. Di sini hanya diperiksa bahwa `hash` memang hasil
+    /// `calculate_hash` dan memenuhi PoW, karena pool diindeks lewat hash tersebut; pemeriksaan
+    /// penuh dilakukan saat blok tertaut. Mengembalikan jumlah blok yang akhirnya tertaut.
+    pub fn add_orphan(&mut self, block: Block) -> Result<usize, ChainError> {
+        if block.hash != block.calculate_hash() || !hash_meets_difficulty(&block.hash, block.difficulty) {
+            return Err(ChainError::InvalidProofOfWork);
+        }
+        self.store_orphan(block, Utc::now().timestamp_millis() as u64);
+        Ok(self.connect_orphans())
+    }
+
+    /// Memasukkan blok ke pool tanpa duplikat; blok tertua dibuang jika pool penuh.
+    fn store_orphan(&mut self, block: Block, received_at: u64) {
+        if self.contains_block(&block.hash) || self.orphans.iter().any(|orphan| orphan.block.hash == block.hash) {
+            return;
+        }
+        if self.orphans.len() >= MAX_ORPHAN_BLOCKS {
+            self.orphans.remove(0);
+        }
+        self.orphans.push(OrphanBlock { block, received_at });
+    }
+
+    /// Membuang orphan yang diterima lebih dari `ttl_ms` sebelum `now_ms`, agar blok yang induknya
+    /// tidak pernah tiba tidak menahan memori. Mengembalikan jumlah blok yang dibuang.
+    pub fn prune_orphan_pool(&mut self, now_ms: u64, ttl_ms: u64) -> usize {
+        let before = self.orphans.len();
+        self.orphans.retain(|orphan| now_ms.saturating_sub(orphan.received_at) <= ttl_ms);
+        before - self.orphans.len()
+    }
+
+    /// Mengevaluasi ulang orphan: yang menunjuk ke tip diverifikasi lalu ditambahkan berurutan,
+    /// sedangkan cabang orphan yang bercabang dari blok lebih lama dicoba sebagai reorg lewat
+    /// `replace_chain`. Cabang yang tidak valid dibuang; cabang yang kerjanya belum cukup disimpan.
+    /// Mengembalikan jumlah blok orphan yang masuk ke rantai.
+    pub fn connect_orphans(&mut self) -> usize {
+        let mut connected = 0;
+        'outer: loop {
+            let block_index = &self.block_index;
+            self.orphans.retain(|orphan| !block_index.contains_key(&orphan.block.hash));
+
+            let tip_hash = &self.blocks.last().unwrap().hash;
+            if let Some(position) = self.orphans.iter().position(|orphan| &orphan.block.previous_hash == tip_hash) {
+                let block = self.orphans.remove(position).block;
+                if self.submit_block(block).is_ok() {
+                    connected += 1;
+                }
+                continue;
+            }
+
+            let mut parents: Vec<String> = self.orphans.iter().map(|orphan| orphan.block.previous_hash.clone()).collect();
+            parents.sort();
+            parents.dedup();
+            for parent in parents {
+                let Some(fork_point) = self.get_block_by_hash(&parent).map(|block| block.index as usize) else {
+                    continue;
+                };
+                let branch = self.orphan_branch(&parent);
+                let branch_work = Self::work_of(branch.iter().map(|&position| &self.orphans[position].block));
+                // Cabang yang jelas lebih ringan tidak perlu di-replay. Body blok yang dipangkas tidak
+                // bisa divalidasi ulang, jadi rantai yang sudah dipangkas hanya bisa diperpanjang di tip.
+                if self.pruned_height > 0 || Self::work_of(self.blocks[..=fork_point].iter().map(Arc::as_ref)).saturating_add(branch_work) <= self.total_work() {
+                    continue;
+                }
+                let mut candidate: Vec<Block> = self.blocks[..=fork_point].iter().map(|block| Block::clone(block)).collect();
+                candidate.extend(branch.iter().map(|&position| self.orphans[position].block.clone()));
+                match self.replace_chain(candidate) {
+                    Ok(false) => continue,
+                    Ok(true) => connected += branch.len(),
+                    Err(_) => {
+                        let hashes: HashSet<String> = branch.iter().map(|&position| self.orphans[position].block.hash.clone()).collect();
+                        self.orphans.retain(|orphan| !hashes.contains(&orphan.block.hash));
+                    }
+                }
+                continue 'outer;
+            }
+            return connected;
+        }
+    }
+
+    /// Posisi orphan pada cabang terpanjang yang dimulai dari anak `parent`, urut dari yang terdekat.
+    fn orphan_branch(&self, parent: &str) -> Vec<usize> {
+        self.orphans
+            .iter()
+            .enumerate()
+            .filter(|(_, orphan)| orphan.block.previous_hash == parent)
+            .map(|(position, orphan)| {
+                let mut branch = vec![position];
+                branch.extend(self.orphan_branch(&orphan.block.hash));
+                branch
+            })
+            .max_by_key(Vec::len)
+            .unwrap_or_default()
+    }
+
+    /// Posisi orphan dari blok terjauh yang masih ada di pool hingga `tip`, atau `None` jika `tip`
+    /// tidak ada di pool.
+    fn orphan_ancestry(&self, tip: &str) -> Option<Vec<usize>> {
+        let mut ancestry = Vec::new();
+        let mut hash = tip;
+        while let Some(position) = self.orphans.iter().position(|orphan| orphan.block.hash == hash) {
+            ancestry.push(position);
+            hash = &self.orphans[position].block.previous_hash;
+        }
+        ancestry.reverse();
+        (!ancestry.is_empty()).then_some(ancestry)
+    }
+
+    /// Jumlah blok di orphan pool.
+    pub fn orphan_count(&self) -> usize {
+        self.orphans.len()
+    }
+
+    /// `Some(true)` jika blok ada di rantai aktif, `Some(false)` jika dikenal tetapi berada di
+    /// cabang stale atau orphan (transaksinya berarti 0 konfirmasi), `None` jika tidak dikenal.
+    pub fn is_on_best_chain(&self, hash: &str) -> Option<bool> {
+        if self.contains_block(hash) {
+            Some(true)
+        } else if self.orphans.iter().any(|orphan| orphan.block.hash == hash) {
+            Some(false)
+        } else {
+            None
+        }
+    }
+
+    /// `(tip_hash, tinggi, kerja kumulatif)` untuk setiap cabang di orphan pool. Kerja cabang yang
+    /// tertaut ke rantai aktif termasuk kerja rantai hingga titik fork-nya; cabang yang induknya
+    /// belum diterima hanya menghitung bloknya sendiri.
+    pub fn fork_tips(&self) -> Vec<(String, u64, u128)> {
+        let parents: HashSet<&str> = self.orphans.iter().map(|orphan| orphan.block.previous_hash.as_str()).collect();
+        self.orphans
+            .iter()
+            .filter(|orphan| !parents.contains(orphan.block.hash.as_str()))
+            .filter_map(|tip| {
+                let ancestry = self.orphan_ancestry(&tip.block.hash)?;
+                let root = &self.orphans[ancestry[0]].block;
+                let prefix_work = self
+                    .get_block_by_hash(&root.previous_hash)
+                    .map_or(0, |fork_point| Self::work_of(self.blocks[..=fork_point.index as usize].iter().map(Arc::as_ref)));
+                let branch_work = Self::work_of(ancestry.iter().map(|&position| &self.orphans[position].block));
+                Some((tip.block.hash.clone(), tip.block.index, prefix_work.saturating_add(branch_work)))
+            })
+            .collect()
+    }
+
+    /// Header cabang yang berakhir di `tip_hash`, dari blok pertama setelah titik fork (atau blok
+    /// terjauh yang dikenal) hingga tip. `None` jika `tip_hash` tidak ada di orphan pool.
+    pub fn fork_branch(&self, tip_hash: &str) -> Option<Vec<BlockHeader>> {
+        let ancestry = self.orphan_ancestry(tip_hash)?;
+        Some(ancestry.iter().map(|&position| self.orphans[position].block.header()).collect())
+    }
+
+    /// Mengadopsi `candidate` jika valid dan memiliki kerja kumulatif lebih besar dari rantai ini.
+    /// `Ok(false)` berarti kandidat valid tetapi tidak lebih baik. Transaksi yang hanya terkonfirmasi
+    /// di cabang lama dikembalikan ke mempool lewat `add_transaction`, lalu `compact_mempool` membuang
+    /// transaksi yang sudah terkonfirmasi atau tidak lagi valid di rantai baru.
+    pub fn replace_chain(&mut self, candidate: Vec<Block>) -> Result<bool, ValidationError> {
+        let replayed = self.replay_candidate(candidate)?;
+        if replayed.total_work() <= self.total_work() {
+            return Ok(false);
+        }
+        let previous = std::mem::replace(&mut self.blocks, replayed.blocks);
+        self.block_index = replayed.block_index;
+        self.confirmed_tx_ids = replayed.confirmed_tx_ids;
+        let pruned_height = std::mem::take(&mut self.pruned_height);
+        self.pruned_balances.clear();
+        self.pruned_tx_ids.clear();
+        self.body_store = None;
+        self.total_supply = self.compute_supply();
+
+        // Blok yang ditinggalkan disimpan sebagai cabang stale; body yang sudah dipangkas tidak.
+        let now = Utc::now().timestamp_millis() as u64;
+        for block in previous.iter().filter(|block| block.index >= pruned_height) {
+            self.store_orphan(Block::clone(block), now);
+        }
+
+        // Transaksi yang juga ada di rantai baru ditolak `add_transaction` sebagai duplikat.
+        for tx in previous.iter().flat_map(|block| &block.transactions).filter(|tx| !tx.is_coinbase()) {
+            let _ = self.add_transaction(tx.clone());
+        }
+        self.compact_mempool();
+        Ok(true)
+    }
+
+    /// Membangun ulang `candidate` dari genesis di rantai sementara dengan parameter konsensus yang
+    /// sama: genesis diperiksa lewat `verify_genesis`, lalu setiap blok berikutnya harus lolos
+    /// `verify_block` (coinbase, signature, saldo, jadwal retarget) sebelum blok selanjutnya dicek.
+    fn replay_candidate(&self, candidate: Vec<Block>) -> Result<Blockchain, ValidationError> {
+        let mut blocks = candidate.into_iter();
+        let genesis = blocks.next().ok_or(ValidationError::InvalidGenesis)?;
+        let mut replayed = Blockchain::with_genesis(genesis, self.params.clone()).map_err(|err| match err {
+            ChainError::Validation(err) => err,
+            _ => ValidationError::InvalidGenesis,
+        })?;
+        for block in blocks {
+            replayed.verify_block(&block)?;
+            replayed.push_block(block);
+        }
+        Ok(replayed)
+    }
+
+    /// Mengecek apakah blok dengan hash tertentu sudah ada di rantai.
+    pub fn contains_block(&self, hash: &str) -> bool {
+        self.get_block_by_hash(hash).is_some()
+    }
+
+    /// Mencari blok berdasarkan hash lewat `block_index` (O(1)). Karena `blocks` publik dan bisa
+    /// diubah langsung, posisi dari indeks dicek ulang dan pencarian linear dipakai jika tidak cocok.
+    pub fn get_block_by_hash(&self, hash: &str) -> Option<&Block> {
+        let indexed = self.block_index.get(hash).and_then(|&position| self.blocks.get(position)).filter(|block| block.hash == hash);
+        indexed.or_else(|| self.blocks.iter().find(|block| block.hash == hash)).map(Arc::as_ref)
+    }
+
+    /// Mengambil blok pada tinggi `index`.
+    pub fn get_block_by_index(&self, index: u64) -> Option<&Block> {
+        self.blocks.get(usize::try_from(index).ok()?).map(Arc::as_ref)
+    }
+
+    /// Membangun ulang `block_index` dan `confirmed_tx_ids` dari `blocks`.
+    fn rebuild_block_index(&mut self) {
+        self.block_index = self.blocks.iter().enumerate().map(|(position, block)| (block.hash.clone(), position)).collect();
+        self.confirmed_tx_ids = self.blocks.iter().flat_map(|block| &block.transactions).map(|tx| tx.id.clone()).collect();
+    }
+
+    /// Menambahkan blok yang sudah divalidasi ke tip beserta entri indeksnya.
+    fn push_block(&mut self, block: Block) {
+        self.block_index.insert(block.hash.clone(), self.blocks.len());
+        self.confirmed_tx_ids.extend(block.transactions.iter().map(|tx| tx.id.clone()));
+        self.blocks.push(Arc::new(block));
+    }
+
+    /// Memisahkan transaksi coinbase (selalu di indeks 0) dari transaksi pengguna dalam sebuah blok.
+    /// `None` juga untuk blok yang body-nya sudah dipangkas.
+    pub fn transactions_in_block(&self, index: u64) -> Option<(&Transaction, &[Transaction])> {
+        let block = self.blocks.get(usize::try_from(index).ok()?)?;
+        block.transactions.split_first()
+    }
+
+    /// Membuang transaksi blok dengan indeks di bawah `height` (tip tidak pernah dipangkas) dan
+    /// hanya menyisakan header-nya, agar memori tidak tumbuh tanpa batas. Efek saldonya dilipat ke
+    /// `pruned_balances`, jadi saldo terkini tetap benar; linkage dan PoW masih bisa divalidasi.
+    /// Id transaksi yang dipangkas disimpan di `pruned_tx_ids` agar replay tetap tertolak.
+    /// Blok dalam jendela `coinbase_maturity` juga tidak dipangkas: `immature_coinbases` membaca
+    /// body-nya, sehingga memangkasnya akan membuat hadiah yang belum matang bisa dibelanjakan.
+    pub fn prune_below(&mut self, height: u64) {
+        let tip = self.blocks.len() as u64 - 1;
+        let end = height.min(tip).min((tip + 1).saturating_sub(self.params.coinbase_maturity));
+        for index in self.pruned_height..end {
+            let block = Arc::make_mut(&mut self.blocks[index as usize]);
+            for tx in std::mem::take(&mut block.transactions) {
+                self.confirmed_tx_ids.remove(&tx.id);
+                if !tx.is_coinbase() {
+                    *self.pruned_balances.entry(tx.from.clone()).or_insert(0) -= tx.total_debit() as i64;
+                    self.pruned_tx_ids.insert(tx.id.clone());
+                }
+                Self::credit_outputs(&mut self.pruned_balances, &tx);
+            }
+        }
+        self.pruned_height = self.pruned_height.max(end);
+    }
+
+    /// Indeks blok pertama yang body-nya masih utuh.
+    pub fn pruned_height(&self) -> u64 {
+        self.pruned_height
+    }
+
+    /// Total kerja kumulatif rantai: jumlah perkiraan hash (`2^difficulty`) untuk setiap blok.
+    /// Dipakai sebagai fork choice oleh `replace_chain`, karena jumlah blok saja menyesatkan
+    /// saat difficulty berbeda-beda.
+    pub fn total_work(&self) -> u128 {
+        Self::work_of(self.blocks.iter().map(Arc::as_ref))
+    }
+
+    fn work_of<'a>(blocks: impl IntoIterator<Item = &'a Block>) -> u128 {
+        blocks.into_iter().fold(0u128, |work, block| work.saturating_add(difficulty_to_expected_hashes(block.difficulty)))
+    }
+
+    /// Ringkasan ringkas untuk dibandingkan dengan node lain.
+    pub fn compact_chain_summary(&self) -> ChainSummary {
+        ChainSummary {
+            height: self.blocks.len() as u64,
+            tip_hash: self.blocks.last().unwrap().hash.clone(),
+            total_work: self.total_work(),
+            genesis_hash: self.blocks[0].hash.clone(),
+        }
+    }
+
+    /// Supply yang beredar, diperbarui setiap kali blok ditambahkan atau rantai diganti.
+    pub fn supply(&self) -> u64 {
+        self.total_supply
+    }
+
+    /// Menurunkan supply dari isi rantai: saldo blok yang dipangkas ditambah koin yang dicetak
+    /// setiap blok. Jalur yang mengganti banyak blok sekaligus (reorg, sinkronisasi) memakai ini
+    /// alih-alih menambah `total_supply` per blok.
+    pub fn compute_supply(&self) -> u64 {
+        let pruned: i64 = self.pruned_balances.values().sum();
+        self.blocks.iter().fold(pruned.max(0) as u64, |supply, block| supply.saturating_add(block.minted_amount()))
+    }
+
+    /// Statistik rantai dalam satu struct, agar pemanggil tidak perlu membaca field internal.
+    pub fn summary(&self) -> ChainStats {
+        let tip = self.blocks.last().unwrap();
+        ChainStats {
+            height: self.blocks.len() as u64,
+            tip_hash: tip.hash.clone(),
+            difficulty: self.next_difficulty().unwrap_or(tip.difficulty),
+            total_supply: self.supply(),
+            mempool_size: self.mempool_size(),
+            total_work: self.total_work(),
+        }
+    }
+
+    /// Mengambil `n` blok terakhir (urut dari yang terlama) untuk ditampilkan tanpa menahan lock rantai.
+    pub fn recent_blocks(&self, n: usize) -> Vec<Arc<Block>> {
+        let start = self.blocks.len().saturating_sub(n);
+        self.blocks[start..].to_vec()
+    }
+
+    /// Mengambil snapshot murah (hanya menyalin pointer `Arc`) dari blok-blok saat ini.
+    pub fn snapshot(&self) -> ChainSnapshot {
+        ChainSnapshot { blocks: self.blocks.clone() }
+    }
+
+    /// Mengekspor header seluruh blok untuk light client (tanpa isi transaksi).
+    pub fn export_headers_only(&self) -> Vec<BlockHeader> {
+        self.blocks.iter().map(|block| block.header()).collect()
+    }
+
+    /// Mengambil blok beserta rantai header dari blok tersebut sampai tip,
+    /// sehingga light client bisa memverifikasi keterkaitannya ke tip yang dipercaya.
+    pub fn get_block_with_proof(&self, index: u64) -> Option<(Block, Vec<BlockHeader>)> {
+        let position = usize::try_from(index).ok()?;
+        let block = Block::clone(self.blocks.get(position)?);
+        let headers = self.blocks[position..].iter().map(|block| block.header()).collect();
+        Some((block, headers))
+    }
+
+    /// Mengekspor header seluruh blok sebagai JSON.
+    pub fn export_headers_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string(&self.export_headers_only())
+    }
+
+    /// Menghitung saldo setiap alamat dari seluruh blok terkonfirmasi.
+    fn confirmed_balances(&self) -> HashMap<String, i64> {
+        let mut balances = self.pruned_balances.clone();
+        for (_, tx) in self.iter_transactions() {
+            if !tx.is_coinbase() {
+                *balances.entry(tx.from.clone()).or_insert(0) -= tx.total_debit() as i64;
+            }
+            Self::credit_outputs(&mut balances, tx);
+        }
+        balances
+    }
+
+    /// Semua transaksi terkonfirmasi secara berurutan, berpasangan dengan blok yang memuatnya.
+    /// Blok yang sudah dipangkas tidak menghasilkan apa pun.
+    pub fn iter_transactions(&self) -> impl Iterator<Item = (&Block, &Transaction)> {
+        self.blocks.iter().flat_map(|block| block.transactions.iter().map(move |tx| (block.as_ref(), tx)))
+    }
+
+    /// Transaksi terkonfirmasi tempat `address` menjadi pengirim atau penerima, dari yang terlama.
+    pub fn transaction_history(&self, address: &str) -> Vec<&Transaction> {
+        self.iter_transactions().map(|(_, tx)| tx).filter(|tx| tx.from == address || tx.outputs().any(|(to, _)| to == address)).collect()
+    }
+
+    /// Saldo terkonfirmasi `address` dari seluruh blok di rantai (tanpa mempool).
+    pub fn get_balance(&self, address: &str) -> i64 {
+        self.confirmed_balance_at_height(address, u64::MAX).expect("Tip rantai tidak pernah dipangkas sebagian")
+    }
+
+    /// Seperti `get_balance`, tetapi tanpa output coinbase yang belum matang
+    /// (lihat `ConsensusParams::coinbase_maturity`). Angka ini yang dipakai `add_transaction`.
+    pub fn spendable_balance(&self, address: &str) -> i64 {
+        let immature: i64 = self.immature_coinbases().map(|tx| tx.amount_received_by(address) as i64).sum();
+        self.get_balance(address) - immature
+    }
+
+    /// Saldo terkonfirmasi semua alamat dikurangi coinbase yang belum matang.
+    fn spendable_balances(&self) -> HashMap<String, i64> {
+        let mut balances = self.confirmed_balances();
+        for tx in self.immature_coinbases() {
+            for (to, amount) in tx.outputs() {
+                *balances.entry(to.to_string()).or_insert(0) -= amount as i64;
+            }
+        }
+        balances
+    }
+
+    /// Coinbase (termasuk alokasi genesis) di blok yang belum cukup dalam dari tip.
+    fn immature_coinbases(&self) -> impl Iterator<Item = &Transaction> {
+        let tip = self.blocks.last().unwrap().index;
+        let maturity = self.params.coinbase_maturity;
+        self.blocks
+            .iter()
+            .rev()
+            .take_while(move |block| block.index.saturating_add(maturity) > tip)
+            .flat_map(|block| block.transactions.iter().filter(|tx| tx.is_coinbase()))
+    }
+
+    /// Saldo historis `address` dengan hanya menghitung blok sampai dan termasuk `height`.
+    /// `None` jika `height` berada di tengah rentang yang sudah dipangkas, karena riwayatnya hilang.
+    pub fn confirmed_balance_at_height(&self, address: &str, height: u64) -> Option<i64> {
+        if height.saturating_add(1) < self.pruned_height {
+            return None;
+        }
+        let mut balance = self.pruned_balances.get(address).copied().unwrap_or(0);
+        for block in self.blocks.iter().take_while(|block| block.index <= height) {
+            for tx in &block.transactions {
+                if !tx.is_coinbase() && tx.from == address {
+                    balance -= tx.total_debit() as i64;
+                }
+                balance += tx.amount_received_by(address) as i64;
+            }
+        }
+        Some(balance)
+    }
+
+    /// Menulis saldo terkonfirmasi saat ini sebagai JSON `{alamat: saldo}` untuk bootstrap atau audit.
+    pub fn export_balance_snapshot(&self, path: &Path) -> io::Result<()> {
+        let balances: BTreeMap<String, i64> = self.confirmed_balances().into_iter().collect();
+        let json = serde_json::to_string_pretty(&balances).map_err(io::Error::other)?;
+        std::fs::write(path, json)
+    }
+
+    /// Menghitung ulang saldo dan memastikan isi berkas snapshot sama persis.
+    pub fn verify_balance_snapshot(&self, path: &Path) -> io::Result<bool> {
+        let contents = std::fs::read_to_string(path)?;
+        let snapshot: HashMap<String, i64> = serde_json::from_str(&contents)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        Ok(snapshot == self.confirmed_balances())
+    }
+
+    /// Saldo yang bisa dibelanjakan ditambah efek transaksi yang masih di mempool.
+    fn provisional_balances(&self) -> HashMap<String, i64> {
+        let mut balances = self.spendable_balances();
+        for tx in &self.pending_transactions {
+            *balances.entry(tx.from.clone()).or_insert(0) -= tx.total_debit() as i64;
+            Self::credit_outputs(&mut balances, tx);
+        }
+        balances
+    }
+
+    /// Memvalidasi transaksi terhadap saldo lalu menerapkannya jika lolos.
+    fn apply_to_balances(balances: &mut HashMap<String, i64>, tx: &Transaction) -> Result<(), TxError> {
+        if tx.is_coinbase() {
+            return Err(TxError::UnexpectedCoinbase);
+        }
+        let required = tx.total_debit();
+        let balance = balances.get(&tx.from).copied().unwrap_or(0);
+        if balance < required as i64 {
+            return Err(TxError::InsufficientBalance { balance, required });
+        }
+        *balances.entry(tx.from.clone()).or_insert(0) -= required as i64;
+        Self::credit_outputs(balances, tx);
+        Ok(())
+    }
+
+    /// Menambahkan setiap output `tx` ke saldo penerimanya.
+    fn credit_outputs(balances: &mut HashMap<String, i64>, tx: &Transaction) {
+        for (to, amount) in tx.outputs() {
+            *balances.entry(to.to_string()).or_insert(0) += amount as i64;
+        }
+    }
+
+    /// Menjalankan transaksi secara berurutan terhadap salinan saldo tanpa mengubah rantai.
+    pub fn simulate_transactions(&self, txs: &[Transaction]) -> Vec<Result<(), TxError>> {
+        let mut balances = self.provisional_balances();
+        txs.iter().map(|tx| Self::apply_to_balances(&mut balances, tx)).collect()
+    }
+
+    /// Memindai transaksi terkonfirmasi dan melaporkan pengirim yang saldonya pernah negatif.
+    pub fn find_double_spends(&self) -> Vec<String> {
+        let mut balances: HashMap<&str, i64> =
+            self.pruned_balances.iter().map(|(address, balance)| (address.as_str(), *balance)).collect();
+        let mut offenders: Vec<String> = Vec::new();
+
+        for block in &self.blocks {
+            for tx in &block.transactions {
+                if !tx.is_coinbase() {
+                    let balance = balances.entry(&tx.from).or_insert(0);
+                    *balance -= tx.total_debit() as i64;
+                    if *balance < 0 && !offenders.contains(&tx.from) {
+                        offenders.push(tx.from.clone());
+                    }
+                }
+                for (to, amount) in tx.outputs() {
+                    *balances.entry(to).or_insert(0) += amount as i64;
+                }
+            }
+        }
+        offenders
+    }
+
+    /// Menambahkan blok tanpa validasi maupun mining (khusus tes).
+    #[cfg(test)]
+    fn append_block_unchecked(&mut self, block: Block) {
+        self.push_block(block);
+    }
+}
+
+// --- Shared Chain ---
+/// Pembungkus `Arc<RwLock<Blockchain>>` untuk dibagi antar thread node/API.
+/// Lock tulis hanya dipegang saat menambahkan blok, bukan selama pencarian PoW.
+#[derive(Debug, Clone)]
+pub struct SharedChain(Arc<RwLock<Blockchain>>);
+
+impl SharedChain {
+    pub fn new(chain: Blockchain) -> Self {
+        SharedChain(Arc::new(RwLock::new(chain)))
+    }
+
+    pub fn read(&self) -> RwLockReadGuard<'_, Blockchain> {
+        self.0.read().expect("Lock rantai teracuni")
+    }
+
+    pub fn write(&self) -> RwLockWriteGuard<'_, Blockchain> {
+        self.0.write().expect("Lock rantai teracuni")
+    }
+
+    /// Menambang satu blok: template diambil di bawah lock, PoW dicari tanpa lock,
+    /// lalu blok ditambahkan di bawah lock tulis. Jika tip berubah selama mining,
+    /// `submit_block` menolak blok dengan `ChainError::NotExtendingTip`. Seperti
+    /// `mine_and_add_block`, template yang terputus disimpan untuk dilanjutkan. Mengembalikan blok yang ditambahkan.
+    pub fn mine_one(&self) -> Result<Arc<Block>, ChainError> {
+        let mut block = self.write().resumable_template()?;
+        let (difficulty, start_nonce) = (block.difficulty, block.nonce);
+        if let Err(err) = mine_block(&mut block, difficulty, start_nonce, interrupt_flag(), None) {
+            if matches!(err, MiningError::Interrupted) {
+                self.write().interrupted_template = Some(block);
+            }
+            return Err(err.into());
+        }
+        let mut chain = self.write();
+        chain.submit_block(block)?;
+        Ok(Arc::clone(chain.blocks.last().unwrap()))
+    }
+}
+
+// --- Format Tampilan ---
+/// Perkiraan jumlah hash yang dibutuhkan untuk memenuhi `difficulty` (2 per bit nol).
+/// Jenuh di `u128::MAX` untuk kesulitan yang terlalu besar.
+pub fn difficulty_to_expected_hashes(difficulty: u32) -> u128 {
+    1u128.checked_shl(difficulty).unwrap_or(u128::MAX)
+}
+
+/// Menampilkan kesulitan sebagai perkiraan jumlah hash, mis. "2^16 ≈ 65536 hash".
+pub fn difficulty_as_human(difficulty: u32) -> String {
+    match 1u128.checked_shl(difficulty) {
+        Some(expected) => format!("2^{} ≈ {} hash", difficulty, expected),
+        None => format!("2^{} hash", difficulty),
+    }
+}
+
+/// Menampilkan hashrate dengan awalan SI, mis. "1.23 MH/s".
+pub fn hashrate_as_human(hashes_per_second: f64) -> String {
+    const UNITS: [&str; 7] = ["H/s", "kH/s", "MH/s", "GH/s", "TH/s", "PH/s", "EH/s"];
+    let mut value = hashes_per_second;
+    let mut unit = 0;
+    while value >= 1000.0 && unit < UNITS.len() - 1 {
+        value /= 1000.0;
+        unit += 1;
+    }
+    format!("{:.2} {}", value, UNITS[unit])
+}
+
+// --- Progress Mining ---
+/// Statistik satu pencarian nonce yang berhasil.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MiningStats {
+    pub nonce: u64,
+    /// Jumlah hash yang dihitung semua thread, termasuk beberapa setelah nonce ditemukan.
+    pub attempts: u64,
+    pub elapsed: Duration,
+    pub hashes_per_second: f64,
+}
+
+/// Tampilan progres mining; berupa trait agar bisa diganti stub saat tes.
+pub trait MiningProgress: Sync {
+    fn update(&self, hashes: u64, elapsed: Duration);
+}
+
+impl MiningProgress for ProgressBar {
+    fn update(&self, hashes: u64, elapsed: Duration) {
+        let hps = hashes as f64 / elapsed.as_secs_f64().max(1.0);
+        self.set_message(format!("Mencari... ({})", hashrate_as_human(hps)));
+    }
+}
+
+/// Membatasi update progres berdasarkan waktu, berapa pun hashrate-nya.
+struct ProgressThrottle<'a, P: MiningProgress> {
+    ui: &'a P,
+    start: Instant,
+    interval_ms: u64,
+    next_update_ms: AtomicU64,
+}
+
+impl<'a, P: MiningProgress> ProgressThrottle<'a, P> {
+    fn new(ui: &'a P, interval: Duration) -> Self {
+        Self {
+            ui,
+            start: Instant::now(),
+            interval_ms: interval.as_millis() as u64,
+            next_update_ms: AtomicU64::new(0),
+        }
+    }
+
+    /// Meneruskan jumlah hash ke UI hanya jika interval sudah lewat. Mengembalikan waktu berjalan
+    /// bagi thread yang melakukan update, agar pemeriksaan periodik lain bisa menumpang di sini.
+    fn record(&self, hashes: u64) -> Option<Duration> {
+        let elapsed = self.start.elapsed();
+        let elapsed_ms = elapsed.as_millis() as u64;
+        let next_update = self.next_update_ms.load(Ordering::Relaxed);
+        if elapsed_ms < next_update {
+            return None;
+        }
+        // Hanya satu thread yang memenangkan slot update ini.
+        self.next_update_ms
+            .compare_exchange(next_update, elapsed_ms + self.interval_ms, Ordering::Relaxed, Ordering::Relaxed)
+            .ok()?;
+        self.ui.update(hashes, elapsed);
+        Some(elapsed)
+    }
+}
+
+/// Mengecek apakah progress bar dimatikan lewat `RELOGICCHAIN_NO_PROGRESS` (dibaca sekali lalu di-cache).
+fn progress_disabled() -> bool {
+    static DISABLED: OnceLock<bool> = OnceLock::new();
+    *DISABLED.get_or_init(|| env_flag_enabled(std::env::var_os(NO_PROGRESS_ENV)))
+}
+
+/// Seperti `NO_COLOR`: nilai apa pun yang tidak kosong (kecuali "0") mengaktifkan flag.
+fn env_flag_enabled(value: Option<OsString>) -> bool {
+    matches!(value, Some(value) if !value.is_empty() && value != "0")
+}
+
+/// Flag pembatalan mining milik proses. Handler Ctrl-C hanya boleh dipasang sekali per proses,
+/// jadi pemasangannya dilakukan pada pemanggilan pertama lalu flag yang sama dipakai ulang.
+/// Jalur library tidak pernah mengosongkan flag, sehingga Ctrl-C yang ditekan di antara dua proses
+/// mining tetap menghentikan proses berikutnya; hanya CLI yang mengosongkannya setelah interupsi ditangani.
+fn interrupt_flag() -> &'static AtomicBool {
+    static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+    static INSTALL_HANDLER: Once = Once::new();
+    INSTALL_HANDLER.call_once(|| {
+        ctrlc::set_handler(|| INTERRUPTED.store(true, Ordering::SeqCst)).expect("Gagal memasang handler Ctrl-C");
+    });
+    &INTERRUPTED
+}
+
+/// Membuat progress bar mining, atau bar tersembunyi jika progres dimatikan.
+fn mining_progress_bar(disabled: bool) -> ProgressBar {
+    if disabled {
+        return ProgressBar::hidden();
+    }
+    let pb = ProgressBar::new_spinner();
+    pb.enable_steady_tick(Duration::from_millis(100));
+    pb.set_style(ProgressStyle::with_template("{spinner:.green} [{elapsed_precise}] {msg}").unwrap());
+    pb
+}
+
+/// Fungsi untuk menambang sebuah blok, mulai dari nonce `start_nonce`. Pencarian berhenti dengan
+/// `MiningError::Interrupted` begitu `cancel` bernilai `true`, atau `MiningError::Timeout` setelah
+/// `timeout` terlewati. Saat berhenti, `block.nonce` diisi nonce pertama yang belum pasti diperiksa,
+/// sehingga pemanggilan berikutnya dengan `start_nonce = block.nonce` melanjutkan tanpa mengulang.
+pub fn mine_block(block: &mut Block, difficulty: u32, start_nonce: u64, cancel: &AtomicBool, timeout: Option<Duration>) -> Result<MiningStats, MiningError> {
+    // Bit nol lebih banyak dari panjang hash tidak akan pernah terpenuhi dan membuat pencarian berjalan selamanya.
+    if difficulty > MAX_DIFFICULTY {
+        return Err(MiningError::InvalidDifficulty(difficulty));
+    }
+
+    search_nonce(block, start_nonce, |hash| meets_difficulty(hash, difficulty), cancel, timeout)
+}
+
+/// Menambang blok sampai hash-nya, dibaca sebagai angka big-endian, `<= target`.
+/// `target` harus 64 karakter hex huruf kecil.
+pub fn mine_block_to_target(block: &mut Block, target: &str, cancel: &AtomicBool) -> Result<MiningStats, MiningError> {
+    let target_bytes = decode_hash_hex(target)
+        .filter(|_| target.bytes().all(|b| b.is_ascii_digit() || (b'a'..=b'f').contains(&b)))
+        .ok_or_else(|| MiningError::InvalidTarget(target.to_string()))?;
+    search_nonce(block, 0, |hash| *hash <= target_bytes, cancel, None)
+}
+
+/// Varian deterministik `mine_block`: mencoba nonce di `nonces` secara berurutan pada satu thread,
+/// tanpa progress bar maupun handler Ctrl-C, dan mengembalikan nonce valid terkecil. Berguna untuk
+/// tes dan CI yang butuh hasil dapat diulang. `MiningError::NoValidNonceFound` jika rentang habis.
+pub fn mine_block_range(block: &mut Block, difficulty: u32, nonces: Range<u64>) -> Result<MiningStats, MiningError> {
+    if difficulty > MAX_DIFFICULTY {
+        return Err(MiningError::InvalidDifficulty(difficulty));
+    }
+
+    let started = Instant::now();
+    let (prefix, suffix) = block.header_without_nonce();
+    let base_hasher = Sha256::new_with_prefix(prefix.as_bytes());
+    let start = nonces.start;
+    let found = nonces.into_iter().find(|nonce| {
+        let mut hasher = base_hasher.clone();
+        hasher.update(nonce.to_string().as_bytes());
+        hasher.update(suffix.as_bytes());
+        meets_difficulty(&hasher.finalize().into(), difficulty)
+    });
+
+    let nonce = found.ok_or(MiningError::NoValidNonceFound)?;
+    block.nonce = nonce;
+    block.hash = block.calculate_hash();
+    let elapsed = started.elapsed();
+    let attempts = nonce - start + 1;
+    let hashes_per_second = attempts as f64 / elapsed.as_secs_f64().max(f64::EPSILON);
+    Ok(MiningStats { nonce, attempts, elapsed, hashes_per_second })
+}
+
+/// Mencari nonce secara paralel mulai dari `start_nonce` sampai `accept` menerima hash blok.
+/// Nonce diperiksa per potongan `NONCE_CHUNK_SIZE`; potongan berikutnya baru dimulai setelah
+/// potongan sebelumnya habis, sehingga awal potongan yang sedang berjalan menjadi checkpoint.
+fn search_nonce(
+    block: &mut Block,
+    start_nonce: u64,
+    accept: impl Fn(&[u8; 32]) -> bool + Sync,
+    cancel: &AtomicBool,
+    timeout: Option<Duration>,
+) -> Result<MiningStats, MiningError> {
+    let pb = mining_progress_bar(progress_disabled());
+
+    let hashes_done = Arc::new(AtomicU64::new(0));
+    let throttle = ProgressThrottle::new(&pb, PROGRESS_UPDATE_INTERVAL);
+
+    // Bagian preimage sebelum nonce di-hash sekali; tiap percobaan hanya meng-clone state hasher.
+    let (prefix, suffix) = block.header_without_nonce();
+    let base_hasher = Sha256::new_with_prefix(prefix.as_bytes());
+    let timed_out = AtomicBool::new(false);
+    let stopped = || cancel.load(Ordering::SeqCst) || timed_out.load(Ordering::Relaxed);
+
+    let mut chunk_start = start_nonce;
+    let found_nonce = loop {
+        let chunk_end = chunk_start.saturating_add(NONCE_CHUNK_SIZE);
+        let found = (chunk_start..chunk_end).into_par_iter().find_any(|&nonce| {
+            if stopped() {
+                return true;
+            }
+
+            let mut hasher = base_hasher.clone();
+            hasher.update(nonce.to_string().as_bytes());
+            hasher.update(suffix.as_bytes());
+            let hash: [u8; 32] = hasher.finalize().into();
+
+            let hashes = hashes_done.fetch_add(1, Ordering::SeqCst);
+            // Batas waktu hanya diperiksa saat update progres, bukan pada setiap nonce.
+            if let (Some(elapsed), Some(limit)) = (throttle.record(hashes), timeout) {
+                if elapsed >= limit {
+                    timed_out.store(true, Ordering::Relaxed);
+                }
+            }
+
+            accept(&hash)
+        });
+        if found.is_some() || stopped() || chunk_end == u64::MAX {
+            break found;
+        }
+        chunk_start = chunk_end;
+    };
+
+    let elapsed = throttle.start.elapsed();
+    pb.finish_and_clear();
+
+    if cancel.load(Ordering::SeqCst) || timed_out.load(Ordering::Relaxed) {
+        block.nonce = chunk_start;
+        return Err(if cancel.load(Ordering::SeqCst) { MiningError::Interrupted } else { MiningError::Timeout });
+    }
+
+    match found_nonce {
+        Some(nonce) => {
+            block.nonce = nonce;
+            block.hash = block.calculate_hash();
+            let attempts = hashes_done.load(Ordering::SeqCst);
+            let hashes_per_second = attempts as f64 / elapsed.as_secs_f64().max(f64::EPSILON);
+            Ok(MiningStats { nonce, attempts, elapsed, hashes_per_second })
+        }
+        None => Err(MiningError::NoValidNonceFound),
+    }
+}
+
+// --- CLI ---
+/// Nilai setelah `flag` di argumen, mis. `--serve <alamat>`; `None` jika flag tidak ada.
+fn flag_value(args: impl IntoIterator<Item = String>, flag: &str) -> Option<String> {
+    let mut args = args.into_iter().skip_while(|arg| arg != flag);
+    args.next()?;
+    args.next()
+}
+
+/// Alamat dari argumen `--serve <alamat>`, jika ada.
+#[cfg(feature = "server")]
+fn serve_address() -> Option<String> {
+    flag_value(std::env::args(), "--serve")
+}
+
+/// Alamat peer dari `--peers <alamat,alamat>`; kosong jika flag tidak ada.
+fn peers_from_args(args: impl IntoIterator<Item = String>) -> Result<Vec<SocketAddr>, ConfigError> {
+    let Some(value) = flag_value(args, "--peers") else {
+        return Ok(Vec::new());
+    };
+    value
+        .split(',')
+        .map(|peer| peer.trim().parse().map_err(|_| ConfigError::InvalidArgument { flag: "--peers", value: peer.to_string() }))
+        .collect()
+}
+
+/// Parameter konsensus default yang bisa ditimpa lewat `--initial-difficulty N`,
+/// mis. agar demo tetap berjalan di perangkat yang lambat.
+fn params_from_args(args: impl IntoIterator<Item = String>) -> Result<ConsensusParams, ConfigError> {
+    let mut params = ConsensusParams::default();
+    if let Some(value) = flag_value(args, "--initial-difficulty") {
+        params.initial_difficulty = value
+            .parse()
+            .map_err(|_| ConfigError::InvalidArgument { flag: "--initial-difficulty", value })?;
+    }
+    params.validate()?;
+    Ok(params)
+}
+
+fn main() {
+    println!("Membuat blockchain baru...");
+    // Kunci demo yang tetap agar miner bisa menandatangani transaksi contoh dari hadiahnya sendiri.
+    let miner_key = SigningKey::from_bytes(&[7u8; 32]);
+    let miner_address = encode_hex(miner_key.verifying_key().as_bytes());
+    let params = match params_from_args(std::env::args()) {
+        Ok(params) => params,
+        Err(e) => {
+            println!("Argumen tidak valid: {:?}", e);
+            return;
+        }
+    };
+    let blockchain = Blockchain::new(miner_address.clone(), params).expect("Parameter konsensus sudah divalidasi");
+    println!("Blok Genesis berhasil dibuat.");
+    println!("Hash: {}", blockchain.blocks[0].hash);
+    println!("Total Supply Awal: {}", blockchain.supply());
+    println!("---");
+    let chain = SharedChain::new(blockchain);
+
+    let peers = match peers_from_args(std::env::args()) {
+        Ok(peers) => peers,
+        Err(e) => {
+            println!("Argumen tidak valid: {:?}", e);
+            return;
+        }
+    };
+    let node = p2p::Node::new(chain.clone(), peers);
+    if let Some(addr) = flag_value(std::env::args(), "--listen") {
+        match node.listen(&addr) {
+            Ok((local_addr, _)) => println!("Node P2P mendengarkan di {}", local_addr),
+            Err(e) => println!("Gagal menjalankan node P2P: {}", e),
+        }
+    }
+    // Node yang baru dimulai mengejar peer lebih dulu; genesis lokal diganti jika rantai peer lebih berat.
+    node.sync_with_peers();
+
+    #[cfg(feature = "server")]
+    if let Some(addr) = serve_address() {
+        match server::spawn(chain.clone(), &addr) {
+            Ok((local_addr, _)) => println!("Server HTTP berjalan di http://{}", local_addr),
+            Err(e) => println!("Gagal menjalankan server HTTP: {}", e),
+        }
+    }
+
+    println!("Tekan Ctrl+C untuk menghentikan mining.");
+
+    loop {
+        {
+            let mut blockchain = chain.write();
+            println!("
+Memulai penambangan untuk blok #{}...", blockchain.blocks.len());
+            if let Ok(difficulty) = blockchain.next_difficulty() {
+                println!("Kesulitan saat ini: {} ({})", difficulty, difficulty_as_human(difficulty));
+            }
+            println!("Total Supply: {}", blockchain.supply());
+
+            // Tambahkan beberapa transaksi contoh dari saldo miner
+            for (to, amount, fee) in [("Bob", 10, 1), ("David", 5, 0)] {
+                let mut tx = Transaction::new(miner_address.clone(), to.into(), amount, String::new()).with_fee(fee);
+                tx.sign(&miner_key);
+                if let Err(e) = blockchain.add_transaction(tx) {
+                    println!("Transaksi contoh ke {} ditolak: {:?}", to, e);
+                }
+            }
+        }
+
+        // PoW dicari tanpa memegang lock agar server dan peer tetap bisa mengakses rantai selama mining.
+        match node.mine_and_broadcast() {
+            Ok(block) => {
+                println!("
+Blok #{} berhasil ditambang!", block.index);
+                println!("  Hash: {}", block.hash);
+                println!("  Nonce: {}", block.nonce);
+                println!("  Kesulitan: {}", block.difficulty);
+                println!("  Hadiah: {}", block.transactions[0].amount);
+            }
+            Err(ChainError::NotExtendingTip) => {
+                println!("
+Tip berubah selama mining (blok dari peer); mengulang dari tip baru.");
+            }
+            Err(ChainError::Mining(MiningError::Interrupted)) => {
+                println!("
+Proses mining dihentikan oleh pengguna.");
+                interrupt_flag().store(false, Ordering::SeqCst);
+                break;
+            }
+            Err(e) => {
+                println!("
+Terjadi error saat mining: {:?}", e);
+                break;
+            }
+        }
+    }
+}
+
+// --- Unit Tests ---
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Membuat rantai dengan genesis yang tidak ditambang, agar tes tidak menunggu PoW.
+    fn test_chain(miner: &str) -> Blockchain {
+        test_chain_with(miner, ConsensusParams::default())
+    }
+
+    fn test_chain_with(miner: &str, params: ConsensusParams) -> Blockchain {
+        let mut chain = Blockchain {
+            blocks: Vec::new(),
+            pending_transactions: Vec::new(),
+            miner_address: miner.to_string(),
+            total_supply: 0,
+            params,
+            mempool_subscribers: Vec::new(),
+            last_mining_duration: None,
+            on_block: None,
+            pruned_height: 0,
+            pruned_balances: HashMap::new(),
+            pruned_tx_ids: HashSet::new(),
+            block_index: HashMap::new(),
+            confirmed_tx_ids: HashSet::new(),
+            difficulty_cache: Mutex::new(None),
+            orphans: Vec::new(),
+            interrupted_template: None,
+            coinbase_recipients: Vec::new(),
+            body_store: None,
+        };
+        let genesis_block = chain.create_genesis_block(&[]);
+        chain.total_supply = chain.get_reward(0);
+        chain.blocks.push(Arc::new(genesis_block));
+        chain.rebuild_block_index();
+        chain
+    }
+
+    /// Mencari nonce mulai dari nonce blok saat ini tanpa progress bar maupun handler Ctrl-C.
+    fn solve(block: &mut Block) {
+        let difficulty = block.difficulty;
+        mine_block_range(block, difficulty, block.nonce..u64::MAX).unwrap();
+    }
+
+    /// Membuat blok berikutnya di atas tip dan menyelesaikan PoW-nya.
+    fn next_block(chain: &Blockchain, transactions: Vec<Transaction>) -> Block {
+        let tip = chain.blocks.last().unwrap();
+        let mut block = Block::new(tip.index + 1, tip.hash.clone(), chain.params.initial_difficulty, transactions);
+        solve(&mut block);
+        block
+    }
+
+    fn easy_params() -> ConsensusParams {
+        ConsensusParams { initial_difficulty: 1, ..ConsensusParams::default() }
+    }
+
+    /// Rantai dengan kesulitan 1 yang genesis-nya sudah memenuhi PoW.
+    fn easy_chain(miner: &str) -> Blockchain {
+        let mut chain = test_chain_with(miner, easy_params());
+        solve(Arc::make_mut(&mut chain.blocks[0]));
+        chain
+    }
+
+    /// Kunci deterministik agar alamat tes stabil antar run.
+    fn signing_key(seed: u8) -> SigningKey {
+        SigningKey::from_bytes(&[seed; 32])
+    }
+
+    /// Alamat (public key hex) milik `key`.
+    fn address(key: &SigningKey) -> String {
+        encode_hex(key.verifying_key().as_bytes())
+    }
+
+    /// Transaksi dari pemilik `key` yang sudah ditandatangani.
+    fn signed_tx(key: &SigningKey, to: &str, amount: u64) -> Transaction {
+        let mut tx = Transaction::new(address(key), to.to_string(), amount, String::new());
+        tx.sign(key);
+        tx
+    }
+
+    #[test]
+    fn test_transaction_hash() {
+        let tx = Transaction::new("a".into(), "b".into(), 10, "s".into());
+        assert!(!tx.id.is_empty());
+    }
+
+    /// Memastikan setiap bagian muncul di `preimage` sesuai urutan yang diberikan.
+    fn assert_in_order(preimage: &str, parts: &[String]) {
+        let mut offset = 0;
+        for part in parts {
+            let position = preimage[offset..].find(part.as_str())
+                .unwrap_or_else(|| panic!("{:?} tidak ditemukan setelah posisi {}", part, offset));
+            offset += position + part.len();
+        }
+        assert_eq!(offset, preimage.len());
+    }
+
+    #[test]
+    fn test_transaction_with_timestamp_is_deterministic() {
+        let a = Transaction::with_timestamp("alice".into(), "bob".into(), 5, "sig".into(), 1_700_000_000_000);
+        let b = Transaction::with_timestamp("alice".into(), "bob".into(), 5, "sig".into(), 1_700_000_000_000);
+        assert_eq!(a.timestamp, 1_700_000_000_000);
+        assert_eq!(a.id, b.id);
+        assert_eq!(a.id, a.calculate_hash());
+
+        let later = Transaction::with_timestamp("alice".into(), "bob".into(), 5, "sig".into(), 1_700_000_000_001);
+        assert_ne!(a.id, later.id);
+    }
+
+    #[test]
+    fn test_block_with_timestamp_is_deterministic() {
+        let build = || {
+            let tx = Transaction::with_timestamp("coinbase".into(), "miner".into(), 50, String::new(), 1_700_000_000_000);
+            Block::with_timestamp(1, "prev".into(), 4, vec![tx], 1_700_000_000_500)
+        };
+        let (a, b) = (build(), build());
+        assert_eq!(a, b);
+        assert_eq!(a.timestamp, 1_700_000_000_500);
+        assert_eq!(a.calculate_hash(), b.calculate_hash());
+        assert_eq!(a.calculate_hash(), sha256_hex(&block_hash_preimage(1, 1_700_000_000_500, "prev", &a.merkle_root, 0, 4)));
+        assert_eq!(Block::builder().index(1).previous_hash("prev").difficulty(4).timestamp(1_700_000_000_500).add_transaction(a.transactions[0].clone()).build(), a);
+    }
+
+    #[test]
+    fn test_transaction_hash_preimage() {
+        let tx = Transaction::with_timestamp("alice".into(), "bob".into(), 42, "sig".into(), 1_700_000_000_000);
+        let preimage = tx.hash_preimage();
+        assert_eq!(preimage, "5:alice3:bob2:421:013:17000000000003:sig7:ed25519");
+        assert_eq!(sha256_hex(&preimage), tx.id);
+
+        // Tanpa batas field, amount 42 + fee 0 dan amount 4 + fee 20 menghasilkan preimage yang sama.
+        let shifted = Transaction::with_timestamp("alice".into(), "bob".into(), 4, "sig".into(), 1_700_000_000_000).with_fee(20);
+        assert_ne!(shifted.hash_preimage(), preimage);
+        assert_ne!(shifted.id, tx.id);
+    }
+
+    #[test]
+    fn test_block_hash_preimage() {
+        let mut block = Block::new(7, "prev".into(), 3, vec![Transaction::coinbase("miner".into(), 50)]);
+        block.nonce = 99;
+        let preimage = block.hash_preimage();
+        assert_in_order(&preimage, &[
+            "7".into(),
+            block.timestamp.to_string(),
+            "prev".into(),
+            block.merkle_root.clone(),
+            "99".into(),
+            "3".into(),
+        ]);
+        assert_eq!(sha256_hex(&preimage), block.calculate_hash());
+    }
+
+    #[test]
+    fn test_block_builder_matches_block_new() {
+        let txs = vec![
+            Transaction::coinbase("miner".into(), 50),
+            Transaction::new("a".into(), "b".into(), 1, "s1".into()),
+            Transaction::new("c".into(), "d".into(), 2, "s2".into()),
+        ];
+        let expected = Block::new(3, "prev".into(), 2, txs.clone());
+
+        let built = Block::builder()
+            .index(3)
+            .previous_hash("prev")
+            .difficulty(2)
+            .timestamp(1_700_000_000_000)
+            .add_transaction(txs[0].clone())
+            .add_transaction(txs[1].clone())
+            .add_transaction(txs[2].clone())
+            .build();
+        assert_eq!(built.merkle_root, expected.merkle_root);
+        assert_eq!(built.index, 3);
+        assert_eq!(built.previous_hash, "prev");
+        assert_eq!(built.difficulty, 2);
+        assert_eq!(built.timestamp, 1_700_000_000_000);
+        assert_eq!(built.transactions.len(), 3);
+        assert!(built.hash.is_empty());
+    }
+
+    #[test]
+    fn test_total_output_amount() {
+        let tx = Transaction::new("a".into(), "b".into(), 42, "s".into());
+        assert_eq!(tx.total_output_amount(), 42);
+        assert_eq!(Transaction::coinbase("miner".into(), 50).total_output_amount(), 50);
+    }
+
+    #[test]
+    fn test_network_frame_round_trip() {
+        let block = Block::new(3, "prev".into(), 2, vec![Transaction::coinbase("miner".into(), 50)]);
+        let mut stream = block.serialize_for_network();
+        let frame_len = stream.len();
+        stream.extend_from_slice(b"sisa");
+
+        let (decoded, consumed) = Block::deserialize_from_network(&stream).unwrap();
+        assert_eq!(consumed, frame_len);
+        assert_eq!(decoded.calculate_hash(), block.calculate_hash());
+        assert_eq!(decoded.transactions, block.transactions);
+    }
+
+    #[test]
+    fn test_network_frame_rejects_wrong_magic() {
+        let mut frame = Block::new(1, "prev".into(), 1, vec![]).serialize_for_network();
+        frame[0] = b'X';
+        assert!(matches!(Block::deserialize_from_network(&frame), Err(NetError::WrongMagic)));
+    }
+
+    #[test]
+    fn test_network_frame_rejects_truncated_frame() {
+        let frame = Block::new(1, "prev".into(), 1, vec![]).serialize_for_network();
+        assert!(matches!(Block::deserialize_from_network(&frame[..frame.len() - 1]), Err(NetError::Truncated)));
+        assert!(matches!(Block::deserialize_from_network(&frame[..5]), Err(NetError::Truncated)));
+    }
+
+    #[test]
+    fn test_merkle_root_not_malleable_by_duplicating_last_leaf() {
+        let a = Transaction::new("a".into(), "b".into(), 1, "s1".into());
+        let b = Transaction::new("c".into(), "d".into(), 2, "s2".into());
+        let c = Transaction::new("e".into(), "f".into(), 3, "s3".into());
+        let odd = MerkleTree::new(&[a.clone(), b.clone(), c.clone()]).build_tree();
+        let padded = MerkleTree::new(&[a, b, c.clone(), c]).build_tree();
+        assert_ne!(odd, padded);
+    }
+
+    #[test]
+    fn test_merkle_leaf_differs_from_internal_node() {
+        let leaf = Transaction::new("a".into(), "b".into(), 1, "s".into());
+        let root = MerkleTree::new(std::slice::from_ref(&leaf)).build_tree();
+        assert_ne!(root, leaf.id, "Leaf tunggal tetap di-hash dengan prefix domain");
+    }
+
+    #[test]
+    fn test_merkle_tree() {
+        let txs = vec![
+            Transaction::new("a".into(), "b".into(), 1, "s1".into()),
+            Transaction::new("c".into(), "d".into(), 2, "s2".into()),
+        ];
+        let mut tree = MerkleTree::new(&txs);
+        let root = tree.build_tree();
+        assert!(!root.is_empty());
+        assert_ne!(root, "0".repeat(64));
+    }
+
+    #[test]
+    fn test_mining_and_valid_proof() {
+        let mut block = Block::new(1, "prev_hash".into(), 12, vec![]);
+        assert!(mine_block(&mut block, 12, 0, &AtomicBool::new(false), None).is_ok());
+        assert!(hash_meets_difficulty(&block.hash, 12));
+        assert_eq!(block.hash, block.calculate_hash());
+    }
+
+    #[test]
+    fn test_mine_block_range_is_reproducible() {
+        let build = || Block::builder().index(1).previous_hash("prev_hash").difficulty(5).timestamp(1_700_000_000_000).build();
+        let mut first = build();
+        let mut second = build();
+        let stats = mine_block_range(&mut first, 5, 1000..5000).unwrap();
+        mine_block_range(&mut second, 5, 1000..5000).unwrap();
+
+        assert_eq!(first.nonce, second.nonce);
+        assert_eq!(first.hash, second.hash);
+        assert!((1000..5000).contains(&first.nonce));
+        assert_eq!(stats.attempts, first.nonce - 1000 + 1);
+        assert!(hash_meets_difficulty(&first.hash, 5));
+
+        let mut narrow = build();
+        let below = first.nonce;
+        assert!(matches!(mine_block_range(&mut narrow, 5, 1000..below), Err(MiningError::NoValidNonceFound)), "Nonce valid terkecil tidak ada di bawahnya");
+        assert_eq!(narrow.nonce, 0, "Blok tidak berubah jika rentang habis");
+    }
+
+    #[test]
+    fn test_mine_block_returns_stats() {
+        let mut block = Block::new(1, "prev_hash".into(), 8, vec![]);
+        let stats = mine_block(&mut block, 8, 0, &AtomicBool::new(false), None).unwrap();
+        assert_eq!(stats.nonce, block.nonce);
+        assert!(stats.attempts >= 1);
+        assert!(stats.hashes_per_second > 0.0);
+        assert!(stats.elapsed > Duration::ZERO);
+    }
+
+    #[test]
+    fn test_meets_difficulty_counts_bits() {
+        let mut hash = [0xffu8; 32];
+        assert!(meets_difficulty(&hash, 0));
+        assert!(!meets_difficulty(&hash, 1));
+        hash[0] = 0x00;
+        hash[1] = 0x1f;
+        assert!(meets_difficulty(&hash, 11));
+        assert!(!meets_difficulty(&hash, 12));
+        assert!(meets_difficulty(&[0u8; 32], MAX_DIFFICULTY));
+        assert!(!meets_difficulty(&[0u8; 32], MAX_DIFFICULTY + 1));
+    }
+
+    #[test]
+    fn test_difficulty_target_matches_meets_difficulty() {
+        let target = difficulty_target(12);
+        assert_eq!(encode_hex(&target), format!("000f{}", "f".repeat(60)));
+        assert!(meets_difficulty(&target, 12), "Target sendiri masih lolos");
+
+        let mut below = target;
+        below[31] = 0x00;
+        assert!(below < target && meets_difficulty(&below, 12));
+        let mut above = target;
+        above[1] = 0x10;
+        assert!(above > target && !meets_difficulty(&above, 12));
+
+        assert_eq!(difficulty_target(0), [0xff; 32]);
+        assert_eq!(difficulty_target(MAX_DIFFICULTY), [0; 32]);
+        assert_eq!(difficulty_target(8)[..2], [0x00, 0xff]);
+    }
+
+    #[test]
+    fn test_mining_to_difficulty_target_meets_difficulty() {
+        let mut block = Block::new(1, "prev_hash".into(), 10, vec![]);
+        mine_block_to_target(&mut block, &encode_hex(&difficulty_target(10)), &AtomicBool::new(false)).unwrap();
+        assert!(hash_meets_difficulty(&block.hash, 10));
+    }
+
+    #[test]
+    fn test_block_json_round_trip_preserves_hash() {
+        let chain = easy_chain("miner");
+        let genesis = &chain.blocks[0];
+        let parsed = Block::from_json(&genesis.to_json()).unwrap();
+        assert_eq!(&parsed, genesis.as_ref());
+        assert_eq!(parsed.calculate_hash(), genesis.hash);
+        assert!(Block::from_json("{}").is_err());
+
+        let tx = &genesis.transactions[0];
+        assert_eq!(serde_json::from_str::<Transaction>(&tx.to_json()).unwrap(), *tx);
+    }
+
+    #[test]
+    fn test_header_without_nonce_matches_preimage() {
+        let mut block = Block::new(9, "prev_hash".into(), 3, vec![Transaction::coinbase("miner".into(), 50)]);
+        block.nonce = 123456;
+        let (prefix, suffix) = block.header_without_nonce();
+        assert_eq!(format!("{}{}{}", prefix, block.nonce, suffix), block.hash_preimage());
+    }
+
+    #[test]
+    fn test_mined_block_validates_with_calculate_hash() {
+        let transactions = (0..50).map(|i| Transaction::new("a".into(), "b".into(), i + 1, "sig".into())).collect();
+        let mut block = Block::new(1, "prev_hash".into(), 10, transactions);
+        mine_block(&mut block, 10, 0, &AtomicBool::new(false), None).unwrap();
+        assert_eq!(block.hash, block.calculate_hash());
+        assert!(hash_meets_difficulty(&block.calculate_hash(), 10));
+    }
+
+    #[test]
+    fn test_mine_block_twice_in_sequence() {
+        for index in 1..=2 {
+            let mut block = Block::new(index, "prev_hash".into(), 2, vec![]);
+            mine_block(&mut block, 2, 0, interrupt_flag(), None).unwrap();
+            assert_eq!(block.hash, block.calculate_hash());
+        }
+    }
+
+    #[test]
+    fn test_mine_block_times_out() {
+        let mut block = Block::new(1, "prev_hash".into(), MAX_DIFFICULTY, vec![]);
+        let started = Instant::now();
+        let result = mine_block(&mut block, MAX_DIFFICULTY, 0, &AtomicBool::new(false), Some(Duration::from_millis(200)));
+        assert!(matches!(result, Err(MiningError::Timeout)));
+        assert!(started.elapsed() >= Duration::from_millis(200));
+        assert!(started.elapsed() < Duration::from_secs(5));
+        assert!(block.hash.is_empty());
+    }
+
+    #[test]
+    fn test_mine_block_stops_when_cancelled() {
+        let mut block = Block::new(1, "prev_hash".into(), MAX_DIFFICULTY, vec![]);
+        assert!(matches!(
+            mine_block(&mut block, MAX_DIFFICULTY, 0, &AtomicBool::new(true), None),
+            Err(MiningError::Interrupted)
+        ));
+    }
+
+    #[test]
+    fn test_mine_block_resumes_from_start_nonce() {
+        let mut block = Block::new(1, "prev_hash".into(), 6, vec![]);
+        let first = mine_block_range(&mut block.clone(), 6, 0..u64::MAX).unwrap().nonce;
+
+        let stats = mine_block(&mut block, 6, first + 1, &AtomicBool::new(false), None).unwrap();
+        assert!(stats.nonce > first, "Rentang di bawah start_nonce dilewati");
+        assert!(hash_meets_difficulty(&block.hash, 6));
+
+        let mut stopped = Block::new(1, "prev_hash".into(), MAX_DIFFICULTY, vec![]);
+        let result = mine_block(&mut stopped, MAX_DIFFICULTY, 1_000, &AtomicBool::new(true), None);
+        assert!(matches!(result, Err(MiningError::Interrupted)));
+        assert_eq!(stopped.nonce, 1_000, "Checkpoint disimpan di block.nonce");
+    }
+
+    #[test]
+    fn test_mine_and_add_block_resumes_interrupted_template() {
+        let mut chain = easy_chain("miner");
+        let mut template = chain.block_template().unwrap();
+        let solution = mine_block_range(&mut template.clone(), 1, 0..u64::MAX).unwrap().nonce;
+        template.nonce = solution + 1;
+        chain.interrupted_template = Some(template.clone());
+
+        chain.mine_and_add_block().unwrap();
+        let mined = chain.blocks.last().unwrap();
+        assert_eq!(mined.timestamp, template.timestamp, "Template yang terputus dipakai ulang");
+        assert!(mined.nonce > solution, "Nonce di bawah checkpoint tidak diulang");
+        assert!(chain.interrupted_template().is_none());
+
+        let stale = chain.block_template().unwrap();
+        chain.mine_and_add_block().unwrap();
+        chain.interrupted_template = Some(stale.clone());
+        chain.mine_and_add_block().unwrap();
+        assert_ne!(chain.blocks.last().unwrap().previous_hash, stale.previous_hash, "Template untuk tip lama dibuang");
+        assert_eq!(chain.blocks.len(), 4);
+    }
+
+    #[test]
+    fn test_mining_rejects_impossible_difficulty() {
+        let mut block = Block::new(1, "prev_hash".into(), MAX_DIFFICULTY + 1, vec![]);
+        assert!(matches!(
+            mine_block(&mut block, MAX_DIFFICULTY + 1, 0, &AtomicBool::new(false), None),
+            Err(MiningError::InvalidDifficulty(257))
+        ));
+        assert!(block.hash.is_empty());
+    }
+
+    #[test]
+    fn test_mine_block_to_target() {
+        let target = format!("0fff{}", "f".repeat(60));
+        let mut block = Block::new(1, "prev_hash".into(), 0, vec![]);
+        mine_block_to_target(&mut block, &target, &AtomicBool::new(false)).unwrap();
+        assert!(block.hash.as_str() <= target.as_str());
+        assert_eq!(block.hash, block.calculate_hash());
+    }
+
+    #[test]
+    fn test_mine_block_to_target_rejects_malformed_target() {
+        let mut block = Block::new(1, "prev_hash".into(), 0, vec![]);
+        assert!(matches!(mine_block_to_target(&mut block, "0fff", &AtomicBool::new(false)), Err(MiningError::InvalidTarget(_))));
+        assert!(matches!(mine_block_to_target(&mut block, &"F".repeat(64), &AtomicBool::new(false)), Err(MiningError::InvalidTarget(_))));
+    }
+
+    #[test]
+    fn test_reward_halving() {
+        let chain = test_chain("test");
+        assert_eq!(chain.get_reward(0), INITIAL_REWARD);
+        assert_eq!(chain.get_reward(HALVING_INTERVAL - 1), INITIAL_REWARD);
+        assert_eq!(chain.get_reward(HALVING_INTERVAL), INITIAL_REWARD / 2);
+        assert_eq!(chain.get_reward(HALVING_INTERVAL * 2), INITIAL_REWARD / 4);
+    }
+
+    #[test]
+    fn test_reward_after_64_halvings_is_zero() {
+        let chain = test_chain("test");
+        assert_eq!(chain.get_reward(HALVING_INTERVAL * 64), 0);
+        assert_eq!(chain.get_reward(HALVING_INTERVAL * 64 + 1), 0);
+        assert_eq!(chain.get_reward(u64::MAX), 0);
+    }
+
+    #[test]
+    fn test_reward_far_past_emission_end_does_not_panic() {
+        let chain = test_chain("test");
+        assert_eq!(chain.get_reward(HALVING_INTERVAL * 100), 0);
+        // 50 >> 6 sudah 0, jauh sebelum batas 64 halving.
+        assert_eq!(chain.get_reward(HALVING_INTERVAL * 6), 0);
+
+        let chain = test_chain_with("test", ConsensusParams { initial_reward: u64::MAX, halving_interval: 1, ..ConsensusParams::default() });
+        assert_eq!(chain.get_reward(63), 1);
+        assert_eq!(chain.get_reward(64), 0);
+    }
+
+    #[test]
+    fn test_reward_schedule_table() {
+        let chain = test_chain("test");
+        let table = chain.reward_schedule_table(HALVING_INTERVAL * 2 + 5);
+        assert_eq!(table, vec![
+            (0..HALVING_INTERVAL, INITIAL_REWARD),
+            (HALVING_INTERVAL..HALVING_INTERVAL * 2, INITIAL_REWARD / 2),
+            (HALVING_INTERVAL * 2..HALVING_INTERVAL * 2 + 5, INITIAL_REWARD / 4),
+        ]);
+        for (range, reward) in &table {
+            assert_eq!(chain.get_reward(range.start), *reward);
+            assert_eq!(chain.get_reward(range.end - 1), *reward);
+        }
+
+        let full = chain.reward_schedule_table(u64::MAX);
+        let (last_range, last_reward) = full.last().unwrap();
+        assert_eq!(*last_reward, 0);
+        assert_eq!(last_range.end, u64::MAX);
+    }
+
+    #[test]
+    fn test_emission_schedule_shows_halvings() {
+        let chain = test_chain("test");
+        assert_eq!(chain.emission_schedule(HALVING_INTERVAL * 2), vec![
+            (0, INITIAL_REWARD),
+            (HALVING_INTERVAL, INITIAL_REWARD / 2),
+            (HALVING_INTERVAL * 2, INITIAL_REWARD / 4),
+        ]);
+        assert_eq!(chain.emission_schedule(HALVING_INTERVAL - 1), vec![(0, INITIAL_REWARD)]);
+        assert_eq!(chain.emission_schedule(u64::MAX).last(), Some(&(HALVING_INTERVAL * 6, 0)), "50 >> 6 = 0");
+    }
+
+    #[test]
+    fn test_average_block_time_from_timestamps() {
+        let mut chain = test_chain("test");
+        assert_eq!(chain.average_block_time(), None);
+        let start = chain.blocks[0].timestamp;
+        for (index, offset_ms) in [(1, 4_000), (2, 10_000), (3, 30_000)] {
+            let mut block = Block::clone(&chain.blocks[0]);
+            block.index = index;
+            block.timestamp = start + offset_ms;
+            chain.append_block_unchecked(block);
+        }
+        assert_eq!(chain.average_block_time(), Some(10.0));
+    }
+
+    #[test]
+    fn test_blocks_until_next_halving_around_boundary() {
+        let mut chain = test_chain("test");
+        while chain.blocks.len() < HALVING_INTERVAL as usize - 1 {
+            let block = Block::clone(chain.blocks.last().unwrap());
+            chain.append_block_unchecked(block);
+        }
+        assert_eq!(chain.blocks_until_next_halving(), 1);
+        assert_eq!(chain.next_halving_height(), HALVING_INTERVAL);
+
+        chain.append_block_unchecked(Block::clone(chain.blocks.last().unwrap()));
+        assert_eq!(chain.blocks_until_next_halving(), HALVING_INTERVAL);
+        assert_eq!(chain.next_halving_height(), HALVING_INTERVAL * 2);
+
+        chain.append_block_unchecked(Block::clone(chain.blocks.last().unwrap()));
+        assert_eq!(chain.blocks_until_next_halving(), HALVING_INTERVAL - 1);
+        assert_eq!(chain.next_halving_height(), HALVING_INTERVAL * 2);
+    }
+
+    #[test]
+    fn test_config_rejects_zero_adjustment_interval() {
+        let config = ConsensusParams { difficulty_adjustment_interval: 0, ..ConsensusParams::default() };
+        assert!(matches!(config.validate(), Err(ConfigError::ZeroDifficultyAdjustmentInterval)));
+        assert!(matches!(
+            Blockchain::new("test".into(), config),
+            Err(ConfigError::ZeroDifficultyAdjustmentInterval)
+        ));
+    }
+
+    #[test]
+    fn test_custom_params_drive_reward_and_difficulty() {
+        let params = ConsensusParams { initial_difficulty: 1, halving_interval: 2, ..ConsensusParams::default() };
+        let mut chain = Blockchain::new("miner".into(), params).unwrap();
+        chain.mine_until(|chain| chain.blocks.len() >= 5).unwrap();
+
+        let rewards: Vec<u64> = chain.blocks.iter().map(|block| block.transactions[0].amount).collect();
+        assert_eq!(rewards, vec![50, 50, 25, 25, 12]);
+        assert!(chain.blocks.iter().all(|block| block.difficulty == 1));
+    }
+
+    #[test]
+    fn test_config_rejects_zero_halving_interval() {
+        let config = ConsensusParams { halving_interval: 0, ..ConsensusParams::default() };
+        assert!(matches!(config.validate(), Err(ConfigError::ZeroHalvingInterval)));
+    }
+
+    #[test]
+    fn test_config_rejects_zero_block_time() {
+        let config = ConsensusParams { block_time_seconds: 0, ..ConsensusParams::default() };
+        assert!(matches!(config.validate(), Err(ConfigError::ZeroBlockTime)));
+    }
+
+    #[test]
+    fn test_config_from_toml_file_validates() {
+        let path = std::env::temp_dir().join(format!("relogicchain-config-{}.toml", std::process::id()));
+        std::fs::write(&path, "halving_interval = 5\ninitial_reward = 100\n").unwrap();
+        let config = ConsensusParams::from_toml_file(&path).unwrap();
+        assert_eq!(config.halving_interval, 5);
+        assert_eq!(config.initial_reward, 100);
+        assert_eq!(config.initial_difficulty, INITIAL_DIFFICULTY);
+
+        std::fs::write(&path, "halving_interval = 0\n").unwrap();
+        assert!(matches!(ConsensusParams::from_toml_file(&path), Err(ConfigError::ZeroHalvingInterval)));
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_coinbase_amount_overflow() {
+        assert_eq!(Blockchain::coinbase_amount(u64::MAX - 1, 1).unwrap(), u64::MAX);
+        assert!(matches!(Blockchain::coinbase_amount(u64::MAX, 1), Err(ChainError::SupplyOverflow)));
+    }
+
+    #[test]
+    fn test_total_supply_overflow_is_rejected() {
+        let mut chain = test_chain("test");
+        chain.total_supply = u64::MAX;
+        chain.pending_transactions.push(Transaction::new("a".into(), "b".into(), 1, "s".into()));
+
+        assert!(matches!(chain.mine_and_add_block(), Err(ChainError::SupplyOverflow)));
+        assert_eq!(chain.total_supply, u64::MAX);
+        assert_eq!(chain.blocks.len(), 1);
+        assert_eq!(chain.pending_transactions.len(), 1, "Mempool tidak boleh dikosongkan saat gagal");
+    }
+
+    #[test]
+    fn test_find_double_spends() {
+        let mut chain = test_chain("miner");
+        let tip = chain.blocks.last().unwrap().hash.clone();
+        chain.append_block_unchecked(Block::new(1, tip, 1, vec![
+            Transaction::coinbase("miner".into(), 50),
+            Transaction::new("miner".into(), "alice".into(), 20, "sig".into()),
+        ]));
+        assert!(chain.find_double_spends().is_empty());
+
+        let tip = chain.blocks.last().unwrap().hash.clone();
+        chain.append_block_unchecked(Block::new(2, tip, 1, vec![
+            Transaction::coinbase("miner".into(), 50),
+            Transaction::new("alice".into(), "bob".into(), 30, "sig".into()),
+        ]));
+        assert_eq!(chain.find_double_spends(), vec!["alice".to_string()]);
+    }
+
+    #[test]
+    fn test_simulate_transactions_reports_overspend() {
+        let chain = test_chain("miner");
+        let txs = vec![
+            Transaction::new("miner".into(), "alice".into(), 20, "sig".into()),
+            Transaction::new("alice".into(), "bob".into(), 10, "sig".into()),
+            Transaction::new("alice".into(), "carol".into(), 15, "sig".into()),
+        ];
+
+        let results = chain.simulate_transactions(&txs);
+        assert_eq!(results[0], Ok(()));
+        assert_eq!(results[1], Ok(()));
+        assert_eq!(results[2], Err(TxError::InsufficientBalance { balance: 10, required: 15 }));
+        assert!(chain.pending_transactions.is_empty(), "Simulasi tidak boleh mengubah mempool");
+        assert_eq!(chain.blocks.len(), 1);
+    }
+
+    #[test]
+    fn test_verify_genesis_rejects_inflated_coinbase() {
+        let chain = test_chain("miner");
+        assert!(chain.verify_genesis(&chain.blocks[0]).is_ok());
+
+        let forged = Block::new(0, "0".repeat(64), INITIAL_DIFFICULTY, vec![
+            Transaction::coinbase("attacker".into(), INITIAL_REWARD * 1000),
+        ]);
+        assert!(matches!(
+            chain.verify_genesis(&forged),
+            Err(ChainError::GenesisRewardMismatch { expected: INITIAL_REWARD, found }) if found == INITIAL_REWARD * 1000
+        ));
+
+        let not_genesis = Block::new(1, "0".repeat(64), INITIAL_DIFFICULTY, vec![Transaction::coinbase("miner".into(), INITIAL_REWARD)]);
+        assert!(matches!(chain.verify_genesis(&not_genesis), Err(ChainError::InvalidGenesis)));
+    }
+
+    #[test]
+    fn test_submit_block_extends_tip() {
+        let mut chain = test_chain_with("miner", easy_params());
+        let block = next_block(&chain, vec![Transaction::coinbase("miner".into(), 50)]);
+        chain.submit_block(block).unwrap();
+        assert_eq!(chain.blocks.len(), 2);
+        assert_eq!(chain.total_supply, 100);
+    }
+
+    #[test]
+    fn test_submit_block_rejects_block_not_extending_tip() {
+        let mut chain = test_chain_with("miner", easy_params());
+        let mut block = Block::new(1, "f".repeat(64), 1, vec![Transaction::coinbase("miner".into(), 50)]);
+        solve(&mut block);
+
+        assert!(matches!(chain.submit_block(block), Err(ChainError::NotExtendingTip)));
+        assert_eq!(chain.blocks.len(), 1);
+    }
+
+    #[test]
+    fn test_add_block_if_new_ignores_duplicates() {
+        let mut chain = easy_chain("miner");
+        let block = next_block(&chain, vec![Transaction::coinbase("miner".into(), 50)]);
+
+        assert!(chain.add_block_if_new(block.clone()).unwrap());
+        assert!(!chain.add_block_if_new(block).unwrap());
+        assert_eq!(chain.blocks.len(), 2);
+
+        let mut invalid = next_block(&chain, vec![Transaction::coinbase("miner".into(), 50)]);
+        invalid.hash = "f".repeat(64);
+        assert!(matches!(chain.add_block_if_new(invalid), Err(ChainError::InvalidProofOfWork)));
+    }
+
+    #[test]
+    fn test_export_headers_only_chains_to_blocks() {
+        let mut chain = easy_chain("miner");
+        for _ in 0..3 {
+            let block = next_block(&chain, vec![Transaction::coinbase("miner".into(), 50)]);
+            chain.submit_block(block).unwrap();
+        }
+
+        let headers = chain.export_headers_only();
+        assert_eq!(headers.len(), chain.blocks.len());
+        for (header, block) in headers.iter().zip(&chain.blocks) {
+            assert_eq!(header.hash, block.hash);
+            assert_eq!(header.calculate_hash(), block.hash);
+        }
+        for pair in headers.windows(2) {
+            assert_eq!(pair[1].previous_hash, pair[0].hash);
+        }
+
+        let parsed: Vec<BlockHeader> = serde_json::from_str(&chain.export_headers_json().unwrap()).unwrap();
+        assert_eq!(parsed, headers);
+    }
+
+    struct CountingProgress {
+        updates: AtomicU64,
+    }
+
+    impl MiningProgress for CountingProgress {
+        fn update(&self, _hashes: u64, _elapsed: Duration) {
+            self.updates.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    #[test]
+    fn test_difficulty_as_human() {
+        assert_eq!(difficulty_to_expected_hashes(16), 65536);
+        assert_eq!(difficulty_as_human(16), "2^16 ≈ 65536 hash");
+        assert_eq!(difficulty_as_human(4), "2^4 ≈ 16 hash");
+        assert_eq!(difficulty_to_expected_hashes(MAX_DIFFICULTY), u128::MAX);
+        assert_eq!(difficulty_as_human(MAX_DIFFICULTY), "2^256 hash");
+    }
+
+    #[test]
+    fn test_hashrate_as_human() {
+        assert_eq!(hashrate_as_human(512.0), "512.00 H/s");
+        assert_eq!(hashrate_as_human(1_234_567.0), "1.23 MH/s");
+        assert_eq!(hashrate_as_human(2_500.0), "2.50 kH/s");
+        assert_eq!(hashrate_as_human(3.0e21), "3000.00 EH/s");
+    }
+
+    #[test]
+    fn test_progress_updates_are_throttled_by_time() {
+        let ui = CountingProgress { updates: AtomicU64::new(0) };
+        let interval = Duration::from_millis(50);
+        let throttle = ProgressThrottle::new(&ui, interval);
+
+        let start = Instant::now();
+        for hashes in 0..200_000 {
+            throttle.record(hashes);
+        }
+        let elapsed_ms = start.elapsed().as_millis() as u64;
+        let updates = ui.updates.load(Ordering::SeqCst);
+        assert!(updates >= 1, "Update pertama harus langsung tampil");
+        assert!(updates <= elapsed_ms / 50 + 1, "{} update dalam {} ms", updates, elapsed_ms);
+        assert!(updates < 200, "Tidak boleh lagi update setiap 1000 hash");
+
+        std::thread::sleep(interval);
+        throttle.record(200_000);
+        assert_eq!(ui.updates.load(Ordering::SeqCst), updates + 1);
+    }
+
+    #[test]
+    fn test_mine_until_height() {
+        let mut chain = easy_chain("miner");
+        let mined = chain.mine_until(|chain| chain.blocks.len() >= 4).unwrap();
+        assert_eq!(mined, 3);
+        assert_eq!(chain.blocks.len(), 4);
+        assert_eq!(chain.mine_until(|chain| chain.blocks.len() >= 4).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_next_difficulty_matches_mined_block() {
+        let params = ConsensusParams { difficulty_adjustment_interval: 2, max_difficulty: 3, ..easy_params() };
+        let mut chain = test_chain_with("miner", params);
+        solve(Arc::make_mut(&mut chain.blocks[0]));
+        for _ in 0..4 {
+            let expected = chain.next_difficulty().unwrap();
+            assert_eq!(chain.next_difficulty().unwrap(), expected);
+            assert_eq!(chain.adjust_difficulty().unwrap(), expected);
+            chain.mine_and_add_block().unwrap();
+            assert_eq!(chain.blocks.last().unwrap().difficulty, expected);
+        }
+    }
+
+    #[test]
+    fn test_time_to_mine_last_block() {
+        let mut chain = easy_chain("miner");
+        assert_eq!(chain.time_to_mine_last_block(), None);
+        chain.mine_and_add_block().unwrap();
+        let elapsed = chain.time_to_mine_last_block().unwrap();
+        assert!(elapsed > Duration::ZERO && elapsed < Duration::from_secs(30));
+    }
+
+    #[test]
+    fn test_replace_genesis_on_empty_chain() {
+        let mut chain = easy_chain("miner");
+        chain.pending_transactions.push(Transaction::new("miner".into(), "alice".into(), 5, "sig".into()));
+        let config = ConsensusParams { initial_reward: 100, ..easy_params() };
+        chain.replace_genesis(config.clone()).unwrap();
+
+        assert_eq!(chain.blocks.len(), 1);
+        assert_eq!(chain.params(), &config);
+        assert_eq!(chain.total_supply, 100);
+        assert!(chain.pending_transactions.is_empty());
+        assert!(chain.verify_genesis(&chain.blocks[0]).is_ok());
+    }
+
+    #[test]
+    fn test_replace_genesis_refuses_non_empty_chain() {
+        let mut chain = easy_chain("miner");
+        let block = next_block(&chain, vec![Transaction::coinbase("miner".into(), 50)]);
+        chain.submit_block(block).unwrap();
+        let tip = chain.blocks.last().unwrap().hash.clone();
+
+        let result = chain.replace_genesis(ConsensusParams { initial_reward: 100, ..easy_params() });
+        assert!(matches!(result, Err(ChainError::ChainNotEmpty { height: 1 })));
+        assert_eq!(chain.blocks.len(), 2);
+        assert_eq!(chain.blocks.last().unwrap().hash, tip);
+        assert_eq!(chain.params(), &easy_params());
+    }
+
+    #[test]
+    fn test_with_genesis_shares_block_zero() {
+        let genesis = Block::clone(&easy_chain("miner").blocks[0]);
+        let node_a = Blockchain::with_genesis(genesis.clone(), easy_params()).unwrap();
+        let node_b = Blockchain::with_genesis(genesis.clone(), easy_params()).unwrap();
+        assert_eq!(node_a.blocks[0].hash, node_b.blocks[0].hash);
+        assert_eq!(node_a.total_supply, 50);
+        assert_eq!(node_a.miner_address, "miner");
+
+        let mut wrong_index = genesis.clone();
+        wrong_index.index = 1;
+        assert!(matches!(Blockchain::with_genesis(wrong_index, easy_params()), Err(ChainError::InvalidGenesis)));
+        let mut wrong_parent = genesis.clone();
+        wrong_parent.previous_hash = "1".repeat(64);
+        assert!(matches!(Blockchain::with_genesis(wrong_parent, easy_params()), Err(ChainError::InvalidGenesis)));
+        let mut tampered = genesis;
+        tampered.nonce += 1;
+        assert!(matches!(
+            Blockchain::with_genesis(tampered, easy_params()),
+            Err(ChainError::Validation(ValidationError::HashMismatch { index: 0 }))
+        ));
+    }
+
+    #[test]
+    fn test_on_block_callback_runs_after_block_is_added() {
+        let mut chain = easy_chain("miner");
+        let counter = Arc::new(AtomicU64::new(0));
+        let seen = Arc::clone(&counter);
+        chain.set_on_block(Box::new(move |block| {
+            seen.fetch_add(1, Ordering::SeqCst);
+            assert_eq!(block.index, seen.load(Ordering::SeqCst));
+        }));
+
+        chain.mine_and_add_block().unwrap();
+        chain.mine_and_add_block().unwrap();
+        assert_eq!(counter.load(Ordering::SeqCst), 2);
+
+        let stale = next_block(&chain, vec![Transaction::coinbase("miner".into(), 50)]);
+        chain.submit_block(stale.clone()).unwrap();
+        assert!(chain.submit_block(stale).is_err());
+        assert_eq!(counter.load(Ordering::SeqCst), 3, "Blok yang ditolak tidak memicu callback");
+    }
+
+    #[test]
+    fn test_genesis_allocations_fund_addresses() {
+        let params = ConsensusParams { initial_difficulty: 4, ..easy_params() };
+        let allocations = vec![("alice".to_string(), 100), ("bob".to_string(), 25)];
+        let chain = Blockchain::new_with_allocations("miner".into(), allocations, params).unwrap();
+        assert_eq!(chain.get_balance("alice"), 100);
+        assert_eq!(chain.get_balance("bob"), 25);
+        assert_eq!(chain.get_balance("miner"), 50);
+        assert_eq!(chain.total_supply, 175);
+        assert!(chain.validate_chain(ValidationLevel::Full).is_ok());
+
+        let overflow = vec![("alice".to_string(), u64::MAX)];
+        assert!(matches!(
+            Blockchain::new_with_allocations("miner".into(), overflow, easy_params()),
+            Err(ConfigError::AllocationOverflow)
+        ));
+    }
+
+    #[test]
+    fn test_add_transaction_rejects_zero_amount() {
+        let mut chain = test_chain("miner");
+        let tx = Transaction::new("miner".into(), "alice".into(), 0, "sig".into());
+        assert_eq!(chain.add_transaction(tx), Err(TxError::ZeroAmount));
+        assert!(chain.pending_transactions.is_empty());
+        assert!(Transaction::coinbase("miner".into(), 0).validate_amount_nonzero_for_outputs().is_ok());
+    }
+
+    #[test]
+    fn test_transaction_validate_rejects_malformed_fields() {
+        let key = signing_key(1);
+        assert_eq!(signed_tx(&key, "alice", 5).validate(), Ok(()));
+        assert_eq!(Transaction::coinbase("miner".into(), 0).validate(), Ok(()));
+
+        assert_eq!(signed_tx(&key, "", 5).validate(), Err(TxError::EmptyAddress));
+        assert_eq!(Transaction::new(String::new(), "alice".into(), 5, "sig".into()).validate(), Err(TxError::EmptyAddress));
+        assert_eq!(signed_tx(&key, "alice", 0).validate(), Err(TxError::ZeroAmount));
+        assert_eq!(signed_tx(&key, &address(&key), 5).validate(), Err(TxError::SelfTransfer));
+    }
+
+    #[test]
+    fn test_add_transaction_rejects_forged_id() {
+        let key = signing_key(1);
+        let mut chain = easy_chain(&address(&key));
+        let mut forged = signed_tx(&key, "alice", 5);
+        forged.id = "a".repeat(64);
+        assert_eq!(forged.validate(), Err(TxError::IdMismatch));
+        assert_eq!(chain.add_transaction(forged), Err(TxError::IdMismatch));
+        assert!(chain.pending_transactions.is_empty());
+    }
+
+    #[test]
+    fn test_block_template_does_not_touch_mempool() {
+        let miner = signing_key(1);
+        let mut chain = easy_chain(&address(&miner));
+        chain.add_transaction(signed_tx(&miner, "alice", 5)).unwrap();
+        let template = chain.block_template().unwrap();
+        assert_eq!(template.index, 1);
+        assert_eq!(template.previous_hash, chain.blocks[0].hash);
+        assert!(template.transactions[0].is_coinbase());
+        assert_eq!(template.transactions.len(), 2);
+        assert_eq!(chain.pending_transactions.len(), 1);
+    }
+
+    #[test]
+    fn test_shared_chain_readers_not_blocked_by_mining() {
+        let shared = SharedChain::new(test_chain_with("miner", ConsensusParams { initial_difficulty: 16, ..ConsensusParams::default() }));
+        let miner = shared.clone();
+        let handle = std::thread::spawn(move || miner.mine_one());
+
+        let mut reads = 0;
+        while !handle.is_finished() {
+            assert!(shared.read().blocks.len() <= 2);
+            reads += 1;
+        }
+        handle.join().unwrap().unwrap();
+        assert!(reads > 0);
+        assert_eq!(shared.read().blocks.len(), 2);
+    }
+
+    #[test]
+    fn test_signature_valid() {
+        let tx = signed_tx(&signing_key(1), "alice", 10);
+        assert!(tx.verify_signature());
+        assert_eq!(tx.id, tx.calculate_hash());
+        assert!(Transaction::coinbase("miner".into(), 50).verify_signature());
+    }
+
+    #[test]
+    fn test_signature_rejects_tampered_amount() {
+        let mut tx = signed_tx(&signing_key(1), "alice", 10);
+        tx.amount = 1_000;
+        assert!(!tx.verify_signature());
+    }
+
+    #[test]
+    fn test_signature_rejects_value_shifted_between_fields() {
+        let tx = signed_tx_with_fee(&signing_key(1), "alice", 12, 3);
+        assert!(tx.verify_signature());
+
+        let mut shifted = tx.clone();
+        (shifted.amount, shifted.fee) = (1, 23);
+        assert!(!shifted.verify_signature(), "Amount 12 + fee 3 tidak boleh sama dengan amount 1 + fee 23");
+    }
+
+    #[test]
+    fn test_signature_rejects_wrong_key() {
+        let mut tx = Transaction::new(address(&signing_key(1)), "alice".into(), 10, String::new());
+        tx.sign(&signing_key(2));
+        assert!(!tx.verify_signature());
+
+        let mut chain = test_chain(&address(&signing_key(1)));
+        assert_eq!(chain.add_transaction(tx), Err(TxError::InvalidSignature));
+        assert!(chain.pending_transactions.is_empty());
+    }
+
+    #[test]
+    fn test_get_balance_after_legitimate_transfer() {
+        let miner = signing_key(1);
+        let mut chain = easy_chain(&address(&miner));
+        let tx = signed_tx(&miner, "alice", 20);
+        chain.add_transaction(tx.clone()).unwrap();
+        assert_eq!(chain.get_balance("alice"), 0, "Transaksi pending belum dihitung");
+
+        let block = next_block(&chain, vec![Transaction::coinbase(address(&miner), 50), tx]);
+        chain.submit_block(block).unwrap();
+        assert_eq!(chain.get_balance("alice"), 20);
+        assert_eq!(chain.get_balance(&address(&miner)), 80);
+    }
+
+    #[test]
+    fn test_add_transaction_counts_pending_outflows() {
+        let miner = signing_key(1);
+        let mut chain = test_chain(&address(&miner));
+        chain.add_transaction(signed_tx(&miner, "alice", 30)).unwrap();
+        assert_eq!(
+            chain.add_transaction(signed_tx(&miner, "bob", 30)),
+            Err(TxError::InsufficientBalance { balance: 20, required: 30 })
+        );
+        assert_eq!(chain.pending_transactions.len(), 1);
+    }
+
+    #[test]
+    fn test_add_transaction_spends_coins_received_in_mempool() {
+        let (miner, alice) = (signing_key(1), signing_key(2));
+        let mut chain = test_chain(&address(&miner));
+        chain.add_transaction(signed_tx(&miner, &address(&alice), 20)).unwrap();
+        chain.add_transaction(signed_tx(&alice, "bob", 15)).unwrap();
+        assert_eq!(
+            chain.add_transaction(signed_tx(&alice, "carol", 10)),
+            Err(TxError::InsufficientBalance { balance: 5, required: 10 })
+        );
+    }
+
+    /// Transaksi bertanda tangan dengan fee tertentu.
+    fn signed_tx_with_fee(key: &SigningKey, to: &str, amount: u64, fee: u64) -> Transaction {
+        let mut tx = Transaction::new(address(key), to.to_string(), amount, String::new()).with_fee(fee);
+        tx.sign(key);
+        tx
+    }
+
+    #[test]
+    fn test_fees_accrue_to_miner() {
+        let miner = signing_key(1);
+        let mut chain = easy_chain(&address(&miner));
+        chain.add_transaction(signed_tx_with_fee(&miner, "alice", 10, 3)).unwrap();
+        chain.add_transaction(signed_tx_with_fee(&miner, "bob", 10, 2)).unwrap();
+        let supply_before = chain.total_supply;
+        chain.mine_and_add_block().unwrap();
+
+        let block = chain.blocks.last().unwrap();
+        assert_eq!(block.transactions[0].amount, 50 + 5);
+        assert_eq!(block.total_fees(), 5);
+        assert_eq!(chain.total_supply, supply_before + 50, "Fee tidak menambah supply");
+        assert_eq!(chain.get_balance(&address(&miner)), 50 - 25 + 55);
+        assert!(chain.pending_transactions.is_empty());
+    }
+
+    #[test]
+    fn test_coinbase_split_follows_recipient_weights() {
+        let miner = signing_key(1);
+        let mut chain = easy_chain(&address(&miner));
+        chain.set_coinbase_recipients(vec![("pool".into(), 70), ("operator".into(), 30)]).unwrap();
+        chain.add_transaction(signed_tx_with_fee(&miner, "alice", 10, 3)).unwrap();
+        let supply_before = chain.total_supply;
+        chain.mine_and_add_block().unwrap();
+
+        // 53 * 70% = 37.1 dan 53 * 30% = 15.9; sisa pembulatan 1 koin jatuh ke penerima pertama.
+        let coinbase = &chain.blocks.last().unwrap().transactions[0];
+        assert_eq!(coinbase.outputs().collect::<Vec<_>>(), vec![("pool", 38), ("operator", 15)]);
+        assert_eq!(coinbase.total_output_amount(), 50 + 3);
+        assert_eq!(chain.get_balance("pool"), 38);
+        assert_eq!(chain.get_balance("operator"), 15);
+        assert_eq!(chain.total_supply, supply_before + 50, "Pembagian tidak mencetak koin tambahan");
+        assert_eq!(chain.supply(), chain.compute_supply());
+        assert!(chain.validate_chain(ValidationLevel::Full).is_ok());
+
+        assert!(matches!(chain.set_coinbase_recipients(vec![("pool".into(), 0)]), Err(ConfigError::InvalidCoinbaseRecipients)));
+        let mut transfer = signed_tx(&miner, "bob", 5);
+        transfer.extra_outputs.push(TxOutput { to: "mallory".into(), amount: 5 });
+        transfer.id = transfer.calculate_hash();
+        assert_eq!(transfer.validate(), Err(TxError::UnexpectedOutputs), "Output tambahan hanya untuk coinbase");
+    }
+
+    #[test]
+    fn test_block_template_selects_dependency_chain() {
+        let keys: Vec<SigningKey> = (1..=4).map(signing_key).collect();
+        let mut chain = easy_chain(&address(&keys[0]));
+        // Setiap transaksi meneruskan koin yang baru diterima, dengan fee makin tinggi di ujung rantai.
+        let hops: Vec<Transaction> = keys.windows(2).enumerate()
+            .map(|(hop, pair)| signed_tx_with_fee(&pair[0], &address(&pair[1]), 40 - 10 * hop as u64, 1 + hop as u64))
+            .collect();
+        let unfunded = signed_tx_with_fee(&signing_key(9), "bob", 5, 50);
+        chain.pending_transactions = hops.iter().rev().cloned().chain([unfunded]).collect();
+
+        let template = chain.block_template().unwrap();
+        assert_eq!(&template.transactions[1..], hops.as_slice());
+        assert!(template.validate_against_balances(&HashMap::from([(address(&keys[0]), 50)])).is_ok());
+    }
+
+    #[test]
+    fn test_add_transaction_debits_amount_plus_fee() {
+        let miner = signing_key(1);
+        let mut chain = test_chain(&address(&miner));
+        assert_eq!(
+            chain.add_transaction(signed_tx_with_fee(&miner, "alice", 45, 6)),
+            Err(TxError::InsufficientBalance { balance: 50, required: 51 })
+        );
+        chain.add_transaction(signed_tx_with_fee(&miner, "alice", 45, 5)).unwrap();
+    }
+
+    #[test]
+    fn test_block_orders_transactions_by_fee() {
+        let (miner, alice) = (signing_key(1), signing_key(2));
+        let mut chain = easy_chain(&address(&miner));
+        let low = signed_tx_with_fee(&miner, "bob", 5, 1);
+        let funding = signed_tx_with_fee(&miner, &address(&alice), 20, 2);
+        let high = signed_tx_with_fee(&miner, "carol", 5, 9);
+        // Bergantung pada `funding`, jadi harus menunggu meski fee-nya tertinggi.
+        let dependent = signed_tx_with_fee(&alice, "dave", 10, 10);
+        for tx in [low.clone(), funding.clone(), high.clone(), dependent.clone()] {
+            chain.add_transaction(tx).unwrap();
+        }
+
+        let template = chain.block_template().unwrap();
+        let ids: Vec<&str> = template.transactions[1..].iter().map(|tx| tx.id.as_str()).collect();
+        assert_eq!(ids, vec![high.id.as_str(), funding.id.as_str(), dependent.id.as_str(), low.id.as_str()]);
+        assert!(template.validate_against_balances(&HashMap::from([(address(&miner), 50)])).is_ok());
+    }
+
+    #[test]
+    fn test_block_respects_max_transactions() {
+        let miner = signing_key(1);
+        let params = ConsensusParams { initial_reward: 1000, max_transactions_per_block: 50, ..easy_params() };
+        let mut chain = test_chain_with(&address(&miner), params);
+        solve(Arc::make_mut(&mut chain.blocks[0]));
+        for i in 0..200 {
+            chain.add_transaction(signed_tx_with_fee(&miner, &format!("user-{}", i), 1, i % 7)).unwrap();
+        }
+        chain.mine_and_add_block().unwrap();
+
+        let block = chain.blocks.last().unwrap();
+        assert_eq!(block.transactions.len(), 50 + 1);
+        assert!(block.transactions[0].is_coinbase());
+        assert_eq!(chain.pending_transactions.len(), 150);
+        assert!(block.transactions[1..].iter().all(|tx| tx.fee >= 5), "Fee tertinggi dipilih lebih dulu");
+    }
+
+    #[test]
+    fn test_submit_block_rejects_oversized_block() {
+        let mut chain = test_chain_with("miner", ConsensusParams { max_transactions_per_block: 1, ..easy_params() });
+        solve(Arc::make_mut(&mut chain.blocks[0]));
+        let block = next_block(&chain, vec![
+            Transaction::coinbase("miner".into(), 50),
+            Transaction::new("miner".into(), "a".into(), 1, "sig".into()),
+            Transaction::new("miner".into(), "b".into(), 1, "sig".into()),
+        ]);
+        assert!(matches!(chain.submit_block(block), Err(ChainError::TooManyTransactions { limit: 1, found: 2 })));
+    }
+
+    #[test]
+    fn test_resubmitting_mined_transaction_is_rejected() {
+        let miner = signing_key(1);
+        let mut chain = easy_chain(&address(&miner));
+        let tx = signed_tx(&miner, "alice", 5);
+        chain.add_transaction(tx.clone()).unwrap();
+        assert_eq!(chain.add_transaction(tx.clone()), Err(TxError::Duplicate), "Sudah ada di mempool");
+
+        chain.mine_and_add_block().unwrap();
+        assert!(chain.contains_transaction(&tx.id));
+        assert!(chain.pending_transactions.is_empty());
+        assert_eq!(chain.add_transaction(tx.clone()), Err(TxError::Duplicate), "Sudah terkonfirmasi di blok");
+
+        let replay = next_block(&chain, vec![Transaction::coinbase(address(&miner), 50), tx.clone()]);
+        assert!(matches!(chain.submit_block(replay), Err(ChainError::DuplicateTransaction(id)) if id == tx.id));
+        assert_eq!(chain.get_balance("alice"), 5);
+    }
+
+    #[test]
+    fn test_block_replaying_confirmed_transaction_under_new_id_is_rejected() {
+        let miner = signing_key(1);
+        let mut chain = easy_chain(&address(&miner));
+        let tx = signed_tx(&miner, "alice", 5);
+        chain.add_transaction(tx.clone()).unwrap();
+        chain.mine_and_add_block().unwrap();
+
+        // Signature tetap sah karena id tidak ikut ditandatangani; hanya id yang diganti.
+        let mut relabeled = tx.clone();
+        relabeled.id = "f".repeat(64);
+        assert!(relabeled.verify_signature());
+        let replay = next_block(&chain, vec![Transaction::coinbase(address(&miner), 50), relabeled]);
+
+        assert_eq!(chain.verify_block(&replay), Err(ValidationError::InvalidTransaction { index: 2, error: TxError::IdMismatch }));
+        assert!(chain.submit_block(replay.clone()).is_err());
+        let mut candidate = candidate_blocks(&chain);
+        candidate.push(replay);
+        assert!(chain.replace_chain(candidate).is_err());
+        assert_eq!(chain.get_balance("alice"), 5);
+    }
+
+    #[test]
+    fn test_full_mempool_evicts_lowest_fee() {
+        let miner = signing_key(1);
+        let mut chain = test_chain_with(&address(&miner), ConsensusParams { max_mempool_size: 3, ..easy_params() });
+        let cheapest = signed_tx_with_fee(&miner, "a", 1, 1);
+        for tx in [signed_tx_with_fee(&miner, "b", 1, 4), cheapest.clone(), signed_tx_with_fee(&miner, "c", 1, 2)] {
+            chain.add_transaction(tx).unwrap();
+        }
+        let events = chain.subscribe_mempool();
+        assert_eq!(chain.mempool_size(), 3);
+
+        let low = signed_tx_with_fee(&miner, "d", 1, 1);
+        assert_eq!(chain.add_transaction(low), Err(TxError::MempoolFull { min_fee: 1 }));
+        assert_eq!(chain.mempool_size(), 3);
+
+        let high = signed_tx_with_fee(&miner, "e", 1, 9);
+        chain.add_transaction(high.clone()).unwrap();
+        assert_eq!(chain.mempool_size(), 3);
+        assert!(!chain.contains_transaction(&cheapest.id));
+        assert!(chain.contains_transaction(&high.id));
+        assert_eq!(events.try_recv(), Ok(MempoolEvent::Removed(cheapest.id, RemovalReason::Evicted)));
+        assert_eq!(events.try_recv(), Ok(MempoolEvent::Added(high.id)));
+    }
+
+    #[test]
+    fn test_evicting_a_parent_drops_its_dependents() {
+        let (miner, alice) = (signing_key(1), signing_key(2));
+        let params = ConsensusParams { max_mempool_size: 3, ..easy_params() };
+        let fund_alice = signed_tx_with_fee(&miner, &address(&alice), 10, 1);
+        let alice_pays = signed_tx_with_fee(&alice, "bob", 5, 5);
+        let other = signed_tx_with_fee(&miner, "carol", 1, 3);
+
+        let mut chain = test_chain_with(&address(&miner), params.clone());
+        for tx in [fund_alice.clone(), alice_pays.clone(), other.clone()] {
+            chain.add_transaction(tx).unwrap();
+        }
+        let events = chain.subscribe_mempool();
+        let incoming = signed_tx_with_fee(&miner, "dave", 1, 2);
+        chain.add_transaction(incoming.clone()).unwrap();
+        assert_eq!(chain.pending_transactions, vec![other.clone(), incoming.clone()], "Transaksi alice kehilangan kreditnya");
+        assert_eq!(events.try_recv(), Ok(MempoolEvent::Removed(fund_alice.id.clone(), RemovalReason::Evicted)));
+        assert_eq!(events.try_recv(), Ok(MempoolEvent::Removed(alice_pays.id.clone(), RemovalReason::Invalid)));
+        assert_eq!(events.try_recv(), Ok(MempoolEvent::Added(incoming.id)));
+
+        // Transaksi baru yang justru membutuhkan kredit dari transaksi yang dikeluarkan ditolak.
+        let mut chain = test_chain_with(&address(&miner), params);
+        for tx in [fund_alice, other, signed_tx_with_fee(&miner, "erin", 1, 4)] {
+            chain.add_transaction(tx).unwrap();
+        }
+        assert_eq!(chain.add_transaction(alice_pays), Err(TxError::InsufficientBalance { balance: 0, required: 10 }));
+        assert_eq!(chain.mempool_size(), 2);
+    }
+
+    #[test]
+    fn test_transaction_history_lists_transfers_for_address() {
+        let miner = signing_key(1);
+        let mut chain = easy_chain(&address(&miner));
+        let to_alice = signed_tx(&miner, "alice", 10);
+        let to_bob = signed_tx(&miner, "bob", 5);
+        chain.add_transaction(to_alice.clone()).unwrap();
+        chain.mine_and_add_block().unwrap();
+        chain.add_transaction(to_bob.clone()).unwrap();
+        chain.mine_and_add_block().unwrap();
+
+        let pairs: Vec<(u64, &str)> = chain.iter_transactions().map(|(block, tx)| (block.index, tx.to.as_str())).collect();
+        let miner_address = address(&miner);
+        assert_eq!(pairs, vec![(0, miner_address.as_str()), (1, miner_address.as_str()), (1, "alice"), (2, miner_address.as_str()), (2, "bob")]);
+
+        assert_eq!(chain.transaction_history("alice"), vec![&to_alice]);
+        assert_eq!(chain.transaction_history("bob"), vec![&to_bob]);
+        assert_eq!(chain.transaction_history(&miner_address).len(), 5, "Tiga coinbase dan dua transfer keluar");
+        assert!(chain.transaction_history("carol").is_empty());
+    }
+
+    #[test]
+    fn test_coinbase_is_spendable_only_after_maturity() {
+        let miner = signing_key(1);
+        let mut chain = test_chain_with(&address(&miner), ConsensusParams { coinbase_maturity: 2, ..easy_params() });
+        solve(Arc::make_mut(&mut chain.blocks[0]));
+        let miner_address = address(&miner);
+        assert_eq!(chain.get_balance(&miner_address), 50);
+        assert_eq!(chain.spendable_balance(&miner_address), 0);
+        assert_eq!(
+            chain.add_transaction(signed_tx(&miner, "alice", 10)),
+            Err(TxError::InsufficientBalance { balance: 0, required: 10 })
+        );
+
+        chain.mine_and_add_block().unwrap();
+        assert_eq!(chain.spendable_balance(&miner_address), 0, "Genesis baru sedalam satu blok");
+        chain.mine_and_add_block().unwrap();
+        assert_eq!(chain.get_balance(&miner_address), 150);
+        assert_eq!(chain.spendable_balance(&miner_address), 50, "Hanya hadiah genesis yang sudah matang");
+        assert!(chain.add_transaction(signed_tx(&miner, "alice", 10)).is_ok());
+        assert_eq!(
+            chain.add_transaction(signed_tx(&miner, "bob", 41)),
+            Err(TxError::InsufficientBalance { balance: 40, required: 41 })
+        );
+    }
+
+    #[test]
+    fn test_add_transaction_rejects_overspend() {
+        let mut chain = test_chain("miner");
+        let tx = signed_tx(&signing_key(2), "bob", 5);
+        assert_eq!(chain.add_transaction(tx), Err(TxError::InsufficientBalance { balance: 0, required: 5 }));
+        assert!(chain.pending_transactions.is_empty());
+    }
+
+    #[test]
+    fn test_subscribe_mempool_reports_added_and_confirmed() {
+        let miner = signing_key(1);
+        let mut chain = easy_chain(&address(&miner));
+        let events = chain.subscribe_mempool();
+
+        let tx = signed_tx(&miner, "alice", 10);
+        let id = tx.id.clone();
+        chain.add_transaction(tx.clone()).unwrap();
+        assert_eq!(events.try_recv().unwrap(), MempoolEvent::Added(id.clone()));
+
+        let block = next_block(&chain, vec![Transaction::coinbase("miner".into(), 50), tx]);
+        chain.submit_block(block).unwrap();
+        assert_eq!(events.try_recv().unwrap(), MempoolEvent::Removed(id, RemovalReason::Confirmed));
+        assert!(chain.pending_transactions.is_empty());
+    }
+
+    #[test]
+    fn test_block_validate_against_balances() {
+        let parent = HashMap::from([("alice".to_string(), 10)]);
+        let block = Block::new(1, "prev".into(), 1, vec![
+            Transaction::coinbase("miner".into(), 50),
+            Transaction::new("alice".into(), "bob".into(), 7, "sig".into()),
+        ]);
+        let after = block.validate_against_balances(&parent).unwrap();
+        assert_eq!(after["alice"], 3);
+        assert_eq!(after["bob"], 7);
+        assert_eq!(after["miner"], 50);
+
+        let overspend = Block::new(1, "prev".into(), 1, vec![
+            Transaction::coinbase("miner".into(), 50),
+            Transaction::new("alice".into(), "bob".into(), 7, "sig".into()),
+            Transaction::new("alice".into(), "carol".into(), 7, "sig".into()),
+        ]);
+        assert_eq!(
+            overspend.validate_against_balances(&parent),
+            Err(TxError::InsufficientBalance { balance: 3, required: 7 })
+        );
+        assert_eq!(parent["alice"], 10, "Saldo induk tidak boleh berubah");
+    }
+
+    #[test]
+    fn test_balance_snapshot_round_trip() {
+        let miner = signing_key(1);
+        let mut chain = easy_chain(&address(&miner));
+        let transfer = signed_tx(&miner, "alice", 20);
+        let block = next_block(&chain, vec![Transaction::coinbase(address(&miner), 50), transfer]);
+        chain.submit_block(block).unwrap();
+
+        let path = std::env::temp_dir().join(format!("relogicchain-balances-{}.json", std::process::id()));
+        chain.export_balance_snapshot(&path).unwrap();
+        assert!(chain.verify_balance_snapshot(&path).unwrap());
+
+        let block = next_block(&chain, vec![Transaction::coinbase(address(&miner), 50)]);
+        chain.submit_block(block).unwrap();
+        assert!(!chain.verify_balance_snapshot(&path).unwrap(), "Snapshot lama tidak lagi cocok");
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_confirmed_balance_at_height() {
+        let (miner, alice) = (signing_key(1), signing_key(2));
+        let mut chain = easy_chain(&address(&miner));
+        let to_alice = signed_tx(&miner, &address(&alice), 30);
+        let block = next_block(&chain, vec![Transaction::coinbase(address(&miner), 50), to_alice]);
+        chain.submit_block(block).unwrap();
+        let to_bob = signed_tx(&alice, "bob", 10);
+        let block = next_block(&chain, vec![Transaction::coinbase(address(&miner), 50), to_bob]);
+        chain.submit_block(block).unwrap();
+
+        let alice = address(&alice);
+        assert_eq!(chain.confirmed_balance_at_height(&alice, 0), Some(0));
+        assert_eq!(chain.confirmed_balance_at_height(&alice, 1), Some(30));
+        assert_eq!(chain.confirmed_balance_at_height(&alice, 2), Some(20));
+        assert_eq!(chain.confirmed_balance_at_height(&address(&miner), 1), Some(70));
+        assert_eq!(chain.confirmed_balance_at_height(&alice, 99), Some(20));
+    }
+
+    #[test]
+    fn test_validate_chain_headers_only_accepts_pruned_bodies() {
+        let mut chain = easy_chain("miner");
+        for _ in 0..2 {
+            let block = next_block(&chain, vec![Transaction::coinbase("miner".into(), 50)]);
+            chain.submit_block(block).unwrap();
+        }
+        assert!(chain.validate_chain(ValidationLevel::Full).is_ok());
+
+        Arc::make_mut(&mut chain.blocks[1]).transactions.clear();
+        assert!(chain.validate_chain(ValidationLevel::HeadersOnly).is_ok());
+        assert!(chain.validate_chain(ValidationLevel::PowAndLinkage).is_ok());
+        assert!(matches!(
+            chain.validate_chain(ValidationLevel::Full),
+            Err(ChainError::Validation(ValidationError::MerkleMismatch { index: 1 }))
+        ));
+    }
+
+    #[test]
+    fn test_validate_chain_levels_on_off_schedule_difficulty() {
+        let mut chain = easy_chain("miner");
+        let tip = chain.blocks.last().unwrap();
+        let mut block = Block::new(1, tip.hash.clone(), 2, vec![Transaction::coinbase("miner".into(), 50)]);
+        solve(&mut block);
+        chain.append_block_unchecked(block);
+
+        assert!(chain.validate_chain(ValidationLevel::PowAndLinkage).is_ok());
+        assert!(matches!(
+            chain.validate_chain(ValidationLevel::HeadersOnly),
+            Err(ChainError::Validation(ValidationError::UnexpectedDifficulty { index: 1, expected: 1, found: 2 }))
+        ));
+
+        Arc::make_mut(&mut chain.blocks[1]).previous_hash = "f".repeat(64);
+        assert!(matches!(
+            chain.validate_chain(ValidationLevel::PowAndLinkage),
+            Err(ChainError::Validation(ValidationError::BrokenLink { index: 1 }))
+        ));
+    }
+
+    /// Rantai kesulitan 1 dengan dua blok setelah genesis.
+    fn valid_chain() -> Blockchain {
+        let mut chain = easy_chain("miner");
+        for _ in 0..2 {
+            let block = next_block(&chain, vec![Transaction::coinbase("miner".into(), 50)]);
+            chain.submit_block(block).unwrap();
+        }
+        chain
+    }
+
+    fn candidate_blocks(chain: &Blockchain) -> Vec<Block> {
+        chain.blocks.iter().map(|block| Block::clone(block)).collect()
+    }
+
+    #[test]
+    fn test_replace_chain_rejects_shorter_chain() {
+        let shorter = valid_chain();
+        let mut chain = valid_chain();
+        let block = next_block(&chain, vec![Transaction::coinbase("miner".into(), 50)]);
+        chain.submit_block(block).unwrap();
+        let tip = chain.blocks.last().unwrap().hash.clone();
+
+        assert_eq!(chain.replace_chain(candidate_blocks(&shorter)), Ok(false));
+        assert_eq!(chain.blocks.last().unwrap().hash, tip);
+    }
+
+    #[test]
+    fn test_replace_chain_rejects_invalid_longer_chain() {
+        let mut chain = valid_chain();
+        let mut candidate = candidate_blocks(&valid_chain());
+        let tip = candidate.last().unwrap().clone();
+        let mut extra = Block::new(tip.index + 1, tip.hash.clone(), 1, vec![Transaction::coinbase("miner".into(), 50)]);
+        solve(&mut extra);
+        candidate.push(extra);
+        candidate[1].nonce += 1;
+
+        assert_eq!(chain.replace_chain(candidate), Err(ValidationError::HashMismatch { index: 1 }));
+        assert_eq!(chain.blocks.len(), 3, "Rantai lama tetap dipakai");
+    }
+
+    #[test]
+    fn test_replace_chain_adopts_valid_longer_chain() {
+        let key = signing_key(1);
+        let mut chain = easy_chain(&address(&key));
+        let tx = signed_tx(&key, "bob", 1);
+        chain.pending_transactions.push(tx.clone());
+
+        let mut longer = easy_chain(&address(&key));
+        longer.blocks = chain.blocks[..1].to_vec();
+        longer.rebuild_block_index();
+        for transactions in [vec![], vec![tx]] {
+            let block = next_block(&longer, [vec![Transaction::coinbase(address(&key), 50)], transactions].concat());
+            longer.submit_block(block).unwrap();
+        }
+
+        assert_eq!(chain.replace_chain(candidate_blocks(&longer)), Ok(true));
+        assert_eq!(chain.blocks.last().unwrap().hash, longer.blocks.last().unwrap().hash);
+        assert_eq!(chain.total_supply, longer.total_supply);
+        assert!(chain.pending_transactions.is_empty(), "Transaksi yang sudah terkonfirmasi keluar dari mempool");
+    }
+
+    #[test]
+    fn test_supply_matches_compute_supply_after_reorg() {
+        let key = signing_key(1);
+        let mut chain = easy_chain(&address(&key));
+        let local = next_block(&chain, vec![Transaction::coinbase(address(&key), 50)]);
+        chain.submit_block(local).unwrap();
+
+        let mut heavier = easy_chain(&address(&key));
+        heavier.blocks = chain.blocks[..1].to_vec();
+        heavier.rebuild_block_index();
+        for to in ["alice", "bob"] {
+            let block = next_block(&heavier, vec![Transaction::coinbase(address(&key), 51), signed_tx_with_fee(&key, to, 5, 1)]);
+            heavier.submit_block(block).unwrap();
+        }
+
+        assert_eq!(chain.replace_chain(candidate_blocks(&heavier)), Ok(true));
+        assert_eq!(chain.supply(), chain.compute_supply());
+        assert_eq!(chain.supply(), 150, "Fee tidak dihitung sebagai koin baru");
+    }
+
+    fn retarget_params() -> ConsensusParams {
+        ConsensusParams { difficulty_adjustment_interval: 2, max_difficulty: 4, ..easy_params() }
+    }
+
+    /// Rantai sepanjang `valid_chain` dengan retarget setiap dua blok. Jika blok 1 ditambang seketika
+    /// setelah genesis, blok 2 naik ke kesulitan 4; jika tepat sesuai target waktu, tetap 1.
+    fn retargeted_chain(instant: bool) -> Blockchain {
+        let mut chain = test_chain_with("miner", retarget_params());
+        solve(Arc::make_mut(&mut chain.blocks[0]));
+        let genesis = &chain.blocks[0];
+        let delay = if instant { 0 } else { 2 * chain.params.block_time_seconds * 1000 };
+        let coinbase = vec![Transaction::coinbase("miner".into(), 50)];
+        let mut block = Block::with_timestamp(1, genesis.hash.clone(), 1, coinbase.clone(), genesis.timestamp + delay);
+        solve(&mut block);
+        let timestamp = block.timestamp;
+        chain.submit_block(block).unwrap();
+
+        let tip = chain.blocks.last().unwrap();
+        let mut block = Block::with_timestamp(2, tip.hash.clone(), chain.next_difficulty().unwrap(), coinbase, timestamp);
+        solve(&mut block);
+        chain.submit_block(block).unwrap();
+        chain
+    }
+
+    #[test]
+    fn test_replace_chain_prefers_more_work_at_equal_length() {
+        let heavier = retargeted_chain(true);
+        let lighter = retargeted_chain(false);
+        assert_eq!(heavier.blocks.len(), lighter.blocks.len());
+        assert_eq!(heavier.total_work(), 2 * difficulty_to_expected_hashes(1) + difficulty_to_expected_hashes(4));
+
+        let mut chain = retargeted_chain(false);
+        assert_eq!(chain.replace_chain(candidate_blocks(&lighter)), Ok(false), "Kerja sama besar bukan alasan reorg");
+        assert_eq!(chain.replace_chain(candidate_blocks(&heavier)), Ok(true));
+        assert_eq!(chain.blocks.last().unwrap().hash, heavier.blocks.last().unwrap().hash);
+        assert_eq!(chain.replace_chain(candidate_blocks(&lighter)), Ok(false));
+    }
+
+    #[test]
+    fn test_replace_chain_requeues_transactions_from_abandoned_branch() {
+        let miner = signing_key(1);
+        let mut chain = easy_chain(&address(&miner));
+        let mut fork = Blockchain::with_genesis(Block::clone(&chain.blocks[0]), easy_params()).unwrap();
+
+        let shared = signed_tx(&miner, "carol", 5);
+        let abandoned = signed_tx(&miner, "alice", 10);
+        let needs_local_reward = signed_tx(&miner, "bob", 80);
+        let local = next_block(&chain, vec![Transaction::coinbase(address(&miner), 50), shared.clone(), abandoned.clone(), needs_local_reward]);
+        chain.submit_block(local).unwrap();
+
+        fork.submit_block(next_block(&fork, vec![Transaction::coinbase("rival".into(), 50), shared])).unwrap();
+        fork.submit_block(next_block(&fork, vec![Transaction::coinbase("rival".into(), 50)])).unwrap();
+
+        assert_eq!(chain.replace_chain(candidate_blocks(&fork)), Ok(true));
+        assert_eq!(chain.pending_transactions, vec![abandoned], "Hanya transaksi yang masih valid dan belum terkonfirmasi yang kembali");
+    }
+
+    #[test]
+    fn test_replace_chain_replays_consensus_rules() {
+        let mut chain = valid_chain();
+        let tip = chain.blocks.last().unwrap().hash.clone();
+
+        let mut inflated = easy_chain("rival");
+        inflated.submit_block(next_block(&inflated, vec![Transaction::coinbase("rival".into(), 50)])).unwrap();
+        for _ in 0..3 {
+            let block = next_block(&inflated, vec![Transaction::coinbase("rival".into(), 1_000_000)]);
+            inflated.append_block_unchecked(block);
+        }
+        assert_eq!(inflated.is_valid(), Ok(()), "Linkage, PoW, dan merkle saja tidak cukup");
+        assert_eq!(chain.replace_chain(candidate_blocks(&inflated)), Err(ValidationError::InvalidCoinbase { index: 2 }));
+
+        let mut forged = easy_chain("rival");
+        let theft = Transaction::new("miner".into(), "rival".into(), 10, "sig".into());
+        for transactions in [vec![Transaction::coinbase("rival".into(), 50)], vec![Transaction::coinbase("rival".into(), 50), theft]] {
+            let block = next_block(&forged, transactions);
+            forged.append_block_unchecked(block);
+        }
+        forged.append_block_unchecked(next_block(&forged, vec![Transaction::coinbase("rival".into(), 50)]));
+        assert!(matches!(
+            chain.replace_chain(candidate_blocks(&forged)),
+            Err(ValidationError::InvalidTransaction { index: 2, error: TxError::InvalidSignature })
+        ));
+
+        let mut rich_genesis = candidate_blocks(&valid_chain());
+        rich_genesis[0].transactions[0].amount = 1_000;
+        assert_eq!(chain.replace_chain(rich_genesis), Err(ValidationError::InvalidGenesis));
+        assert_eq!(chain.blocks.last().unwrap().hash, tip, "Rantai lokal tidak berubah");
+    }
+
+    #[test]
+    fn test_pruned_transaction_cannot_be_replayed() {
+        let miner = signing_key(1);
+        let mut chain = easy_chain(&address(&miner));
+        let tx = signed_tx(&miner, "alice", 30);
+        chain.add_transaction(tx.clone()).unwrap();
+        chain.mine_and_add_block().unwrap();
+        chain.mine_and_add_block().unwrap();
+        chain.prune_below(2);
+        assert!(chain.transactions_in_block(1).is_none());
+
+        assert!(chain.contains_transaction(&tx.id));
+        assert_eq!(chain.add_transaction(tx.clone()), Err(TxError::Duplicate));
+        let replay = next_block(&chain, vec![Transaction::coinbase(address(&miner), 50), tx.clone()]);
+        assert!(matches!(chain.submit_block(replay), Err(ChainError::DuplicateTransaction(id)) if id == tx.id));
+
+        let path = std::env::temp_dir().join(format!("relogicchain-pruned-ids-{}.json", std::process::id()));
+        chain.save_to_file(&path).unwrap();
+        let mut loaded = Blockchain::load_from_file(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(loaded.add_transaction(tx), Err(TxError::Duplicate), "Id yang dipangkas ikut tersimpan");
     }
 
     #[test]
-    fn test_reward_halving() {
-        let chain = Blockchain::new("test".into());
-        assert_eq!(chain.get_reward(0), INITIAL_REWARD);
-        assert_eq!(chain.get_reward(HALVING_INTERVAL - 1), INITIAL_REWARD);
-        assert_eq!(chain.get_reward(HALVING_INTERVAL), INITIAL_REWARD / 2);
-        assert_eq!(chain.get_reward(HALVING_INTERVAL * 2), INITIAL_REWARD / 4);
+    fn test_prune_below_keeps_headers_and_balances() {
+        let miner = signing_key(1);
+        let mut chain = easy_chain(&address(&miner));
+        chain.add_transaction(signed_tx(&miner, "alice", 30)).unwrap();
+        chain.mine_and_add_block().unwrap();
+        chain.mine_and_add_block().unwrap();
+        let balance = chain.get_balance(&address(&miner));
+        let supply = chain.total_supply;
+
+        chain.prune_below(2);
+        assert_eq!(chain.pruned_height(), 2);
+        assert!(chain.transactions_in_block(1).is_none(), "Body blok yang dipangkas sudah dibuang");
+        assert!(chain.transactions_in_block(2).is_some());
+        assert_eq!(chain.is_valid(), Ok(()), "Linkage dan PoW tetap bisa divalidasi");
+        assert!(chain.validate_chain(ValidationLevel::Full).is_ok());
+        assert_eq!(chain.get_balance("alice"), 30);
+        assert_eq!(chain.get_balance(&address(&miner)), balance);
+        assert_eq!(chain.confirmed_balance_at_height("alice", 0), None, "Riwayat di tengah rentang pangkasan hilang");
+        assert_eq!(chain.confirmed_balance_at_height("alice", 1), Some(30));
+
+        let path = std::env::temp_dir().join(format!("relogicchain-pruned-{}.json", std::process::id()));
+        chain.save_to_file(&path).unwrap();
+        let loaded = Blockchain::load_from_file(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(loaded.total_supply, supply);
+        assert_eq!(loaded.get_balance("alice"), 30);
+        assert_eq!(loaded.pruned_height(), 2);
+    }
+
+    #[test]
+    fn test_warm_start_validates_headers_and_loads_bodies_on_first_access() {
+        let miner = signing_key(1);
+        let mut chain = easy_chain(&address(&miner));
+        chain.add_transaction(signed_tx(&miner, "alice", 5)).unwrap();
+        chain.mine_and_add_block().unwrap();
+        chain.mine_and_add_block().unwrap();
+        let dir = std::env::temp_dir().join(format!("relogicchain-warm-{}", std::process::id()));
+        chain.save_warm(&dir).unwrap();
+
+        let warm = Blockchain::warm_start(&dir).unwrap();
+        let cached = |chain: &Blockchain| chain.body_store.as_ref().unwrap().cache.lock().unwrap().len();
+        assert_eq!(cached(&warm), 0, "Start hanya membaca header");
+        assert_eq!(warm.pruned_height(), 2);
+        assert!(warm.transactions_in_block(1).is_none());
+        assert_eq!(warm.get_balance("alice"), 5, "Saldo dari snapshot, tanpa body");
+        assert!(warm.contains_transaction(&chain.blocks[1].transactions[1].id));
+
+        assert_eq!(warm.fetch_body(1).unwrap(), chain.blocks[1].transactions);
+        assert_eq!(cached(&warm), 1);
+        std::fs::remove_file(dir.join("bodies").join("1.json")).unwrap();
+        assert_eq!(warm.fetch_body(1).unwrap(), chain.blocks[1].transactions, "Body kedua kali dilayani dari cache");
+        assert!(warm.validate_chain(ValidationLevel::Full).is_ok(), "Validasi penuh memuat body genesis dan me-replay saldo");
+
+        std::fs::write(dir.join("bodies").join("0.json"), "[]").unwrap();
+        let tampered = Blockchain::warm_start(&dir).unwrap();
+        assert!(matches!(tampered.fetch_body(0), Err(StorageError::BodyUnavailable { index: 0 })));
+        assert!(matches!(
+            tampered.validate_chain(ValidationLevel::Full),
+            Err(ChainError::Validation(ValidationError::MissingBody { index: 0 }))
+        ));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_prune_below_never_prunes_tip() {
+        let mut chain = valid_chain();
+        chain.prune_below(u64::MAX);
+        assert_eq!(chain.pruned_height(), 2);
+        assert!(chain.transactions_in_block(2).is_some());
+        assert_eq!(chain.is_valid(), Ok(()));
+    }
+
+    #[test]
+    fn test_prune_below_keeps_immature_coinbases_unspendable() {
+        let miner = signing_key(1);
+        let miner_address = address(&miner);
+        let mut chain = test_chain_with(&miner_address, ConsensusParams { coinbase_maturity: 2, ..easy_params() });
+        solve(Arc::make_mut(&mut chain.blocks[0]));
+        for _ in 0..3 {
+            let block = next_block(&chain, vec![Transaction::coinbase(miner_address.clone(), 50)]);
+            chain.submit_block(block).unwrap();
+        }
+        let spendable = chain.spendable_balance(&miner_address);
+        assert_eq!(spendable, 100, "Hadiah blok #2 dan #3 belum matang");
+
+        chain.prune_below(u64::MAX);
+        assert_eq!(chain.pruned_height(), 2, "Blok dalam jendela maturity tetap utuh");
+        assert_eq!(chain.spendable_balance(&miner_address), spendable);
+        assert_eq!(
+            chain.add_transaction(signed_tx(&miner, "alice", 150)),
+            Err(TxError::InsufficientBalance { balance: 100, required: 150 })
+        );
+    }
+
+    /// Rantai milik `miner` beserta blok berikutnya yang valid dan berisi satu transfer bertanda tangan.
+    fn chain_and_candidate(miner: &SigningKey) -> (Blockchain, Block) {
+        let chain = easy_chain(&address(miner));
+        let block = next_block(&chain, vec![Transaction::coinbase(address(miner), 50), signed_tx(miner, "alice", 5)]);
+        (chain, block)
+    }
+
+    #[test]
+    fn test_verify_block_accepts_valid_block() {
+        let (chain, block) = chain_and_candidate(&signing_key(1));
+        assert_eq!(chain.verify_block(&block), Ok(()));
+        assert_eq!(chain.blocks.len(), 1, "Verifikasi tidak menambahkan blok");
+    }
+
+    #[test]
+    fn test_verify_block_rejects_bad_link_and_index() {
+        let (chain, block) = chain_and_candidate(&signing_key(1));
+        let mut orphan = block.clone();
+        orphan.previous_hash = "f".repeat(64);
+        solve(&mut orphan);
+        assert_eq!(chain.verify_block(&orphan), Err(ValidationError::BrokenLink { index: 1 }));
+
+        let mut skipped = block;
+        skipped.index = 5;
+        solve(&mut skipped);
+        assert_eq!(chain.verify_block(&skipped), Err(ValidationError::IndexMismatch { index: 1, found: 5 }));
+    }
+
+    #[test]
+    fn test_verify_block_rejects_bad_proof_of_work() {
+        let (chain, block) = chain_and_candidate(&signing_key(1));
+        let mut tampered = block.clone();
+        tampered.nonce += 1;
+        assert_eq!(chain.verify_block(&tampered), Err(ValidationError::HashMismatch { index: 1 }));
+
+        let mut weak = block;
+        loop {
+            weak.nonce += 1;
+            weak.hash = weak.calculate_hash();
+            if !hash_meets_difficulty(&weak.hash, weak.difficulty) {
+                break;
+            }
+        }
+        assert_eq!(chain.verify_block(&weak), Err(ValidationError::BadProofOfWork { index: 1 }));
+    }
+
+    #[test]
+    fn test_verify_block_rejects_unexpected_difficulty() {
+        let (chain, mut block) = chain_and_candidate(&signing_key(1));
+        block.difficulty = 3;
+        solve(&mut block);
+        assert_eq!(chain.verify_block(&block), Err(ValidationError::UnexpectedDifficulty { index: 1, expected: 1, found: 3 }));
+    }
+
+    #[test]
+    fn test_verify_block_rejects_merkle_mismatch() {
+        let (chain, mut block) = chain_and_candidate(&signing_key(1));
+        block.transactions.pop();
+        solve(&mut block);
+        assert_eq!(chain.verify_block(&block), Err(ValidationError::MerkleMismatch { index: 1 }));
+    }
+
+    #[test]
+    fn test_verify_block_rejects_invalid_signature() {
+        let miner = signing_key(1);
+        let chain = easy_chain(&address(&miner));
+        let forged = Transaction::new(address(&miner), "mallory".into(), 50, "00".repeat(64));
+        let block = next_block(&chain, vec![Transaction::coinbase(address(&miner), 50), forged]);
+        assert_eq!(
+            chain.verify_block(&block),
+            Err(ValidationError::InvalidTransaction { index: 1, error: TxError::InvalidSignature })
+        );
+    }
+
+    #[test]
+    fn test_verify_block_rejects_inflated_coinbase() {
+        let miner = signing_key(1);
+        let chain = easy_chain(&address(&miner));
+        let paid = signed_tx_with_fee(&miner, "alice", 5, 3);
+        let correct = next_block(&chain, vec![Transaction::coinbase(address(&miner), 53), paid.clone()]);
+        assert_eq!(chain.verify_block(&correct), Ok(()), "Hadiah 50 ditambah fee 3");
+
+        let inflated = next_block(&chain, vec![Transaction::coinbase(address(&miner), 1_000_000), paid.clone()]);
+        assert_eq!(chain.verify_block(&inflated), Err(ValidationError::InvalidCoinbase { index: 1 }));
+
+        let second = next_block(&chain, vec![Transaction::coinbase(address(&miner), 50), Transaction::coinbase(address(&miner), 50)]);
+        assert_eq!(chain.verify_block(&second), Err(ValidationError::InvalidCoinbase { index: 1 }));
+
+        let missing = next_block(&chain, vec![paid]);
+        assert_eq!(chain.verify_block(&missing), Err(ValidationError::InvalidCoinbase { index: 1 }));
+    }
+
+    #[test]
+    fn test_submit_block_rejects_inflated_coinbase() {
+        let miner = signing_key(1);
+        let mut chain = easy_chain(&address(&miner));
+        let inflated = next_block(&chain, vec![Transaction::coinbase(address(&miner), 1_000_000)]);
+        assert!(matches!(
+            chain.submit_block(inflated),
+            Err(ChainError::Validation(ValidationError::InvalidCoinbase { index: 1 }))
+        ));
+        assert_eq!(chain.blocks.len(), 1);
+        assert_eq!(chain.total_supply, chain.compute_supply());
+
+        let forged = signed_tx(&signing_key(2), "alice", 5);
+        let unsigned = next_block(&chain, vec![Transaction::coinbase(address(&miner), 50), Transaction { signature: "sig".into(), ..forged }]);
+        assert!(matches!(
+            chain.submit_block(unsigned),
+            Err(ChainError::Validation(ValidationError::InvalidTransaction { index: 1, .. }))
+        ));
+    }
+
+    #[test]
+    fn test_verify_block_rejects_overspend_within_block() {
+        let miner = signing_key(1);
+        let underfunded = signing_key(2);
+        let mut chain = easy_chain(&address(&miner));
+        let funding = next_block(&chain, vec![Transaction::coinbase(address(&miner), 50), signed_tx(&miner, &address(&underfunded), 30)]);
+        chain.submit_block(funding).unwrap();
+
+        let first = signed_tx(&underfunded, "alice", 20);
+        let second = signed_tx(&underfunded, "bob", 20);
+        let block = next_block(&chain, vec![Transaction::coinbase(address(&miner), 50), first.clone(), second.clone()]);
+        assert_eq!(chain.verify_block(&block), Err(ValidationError::DoubleSpend { index: 2, tx_id: second.id }));
+
+        let affordable = next_block(&chain, vec![Transaction::coinbase(address(&miner), 50), first]);
+        assert_eq!(chain.verify_block(&affordable), Ok(()));
+    }
+
+    #[test]
+    fn test_overspend_rejected_on_every_acceptance_path() {
+        let miner = signing_key(1);
+        let underfunded = signing_key(2);
+        let mut chain = easy_chain(&address(&miner));
+        let funding = next_block(&chain, vec![Transaction::coinbase(address(&miner), 50), signed_tx(&miner, &address(&underfunded), 30)]);
+        chain.submit_block(funding).unwrap();
+
+        let second = signed_tx(&underfunded, "bob", 20);
+        let overspend = next_block(&chain, vec![Transaction::coinbase(address(&miner), 50), signed_tx(&underfunded, "alice", 20), second.clone()]);
+        let double_spend = ValidationError::DoubleSpend { index: 2, tx_id: second.id };
+
+        assert!(matches!(chain.submit_block(overspend.clone()), Err(ChainError::Validation(err)) if err == double_spend));
+        assert!(matches!(chain.add_block_if_new(overspend.clone()), Err(ChainError::Validation(err)) if err == double_spend));
+
+        let child = child_block(&overspend, &address(&miner));
+        let mut candidate = candidate_blocks(&chain);
+        candidate.extend([overspend.clone(), child]);
+        assert_eq!(chain.replace_chain(candidate), Err(double_spend));
+
+        assert_eq!(chain.add_orphan(overspend).unwrap(), 0, "Orphan yang overspend tidak ikut tertaut");
+        assert_eq!(chain.orphan_count(), 0);
+        assert_eq!(chain.blocks.len(), 2);
+    }
+
+    #[test]
+    fn test_mempool_survives_save_and_load() {
+        let key = signing_key(1);
+        let mut chain = easy_chain(&address(&key));
+        let txs: Vec<Transaction> = ["alice", "bob", "carol"].iter().map(|to| signed_tx(&key, to, 5)).collect();
+        for tx in &txs {
+            chain.add_transaction(tx.clone()).unwrap();
+        }
+
+        let path = std::env::temp_dir().join(format!("relogicchain-mempool-{}.json", std::process::id()));
+        chain.save_mempool(&path).unwrap();
+        chain.pending_transactions.clear();
+        let block = next_block(&chain, vec![Transaction::coinbase(address(&key), 50), txs[0].clone()]);
+        chain.submit_block(block).unwrap();
+
+        let report = chain.load_mempool(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(report, MempoolSyncReport { accepted: 2, rejected: 0, duplicate: 1 }, "Transaksi yang sudah ditambang tidak dipulihkan");
+        assert_eq!(chain.pending_transactions, txs[1..].to_vec());
+    }
+
+    /// Blok anak `parent` dengan coinbase untuk `miner`, ditambang pada kesulitan 1.
+    fn child_block(parent: &Block, miner: &str) -> Block {
+        let mut block = Block::new(parent.index + 1, parent.hash.clone(), 1, vec![Transaction::coinbase(miner.into(), 50)]);
+        solve(&mut block);
+        block
+    }
+
+    #[test]
+    fn test_orphans_connect_when_parent_arrives() {
+        let mut chain = easy_chain("miner");
+        let first = child_block(&chain.blocks[0], "miner");
+        let second = child_block(&first, "miner");
+
+        assert_eq!(chain.add_orphan(second.clone()).unwrap(), 0);
+        assert_eq!(chain.orphan_count(), 1);
+        assert_eq!(chain.blocks.len(), 1);
+
+        assert_eq!(chain.add_orphan(first).unwrap(), 2);
+        assert_eq!(chain.orphan_count(), 0);
+        assert_eq!(chain.blocks.last().unwrap().hash, second.hash);
+        assert_eq!(chain.is_valid(), Ok(()));
+    }
+
+    #[test]
+    fn test_orphan_branch_triggers_reorg_when_heavier() {
+        let mut chain = easy_chain("miner");
+        let local = child_block(&chain.blocks[0], "miner");
+        chain.submit_block(local.clone()).unwrap();
+
+        let fork_first = child_block(&chain.blocks[0], "rival");
+        let fork_second = child_block(&fork_first, "rival");
+        assert_eq!(chain.add_orphan(fork_first.clone()).unwrap(), 0, "Cabang sepanjang rantai lokal belum lebih berat");
+        assert_eq!(chain.blocks.last().unwrap().hash, local.hash);
+        assert_eq!(chain.is_on_best_chain(&fork_first.hash), Some(false));
+
+        assert_eq!(chain.add_orphan(fork_second.clone()).unwrap(), 2);
+        assert_eq!(chain.blocks.last().unwrap().hash, fork_second.hash);
+        assert_eq!(chain.orphan_count(), 1, "Blok lokal yang ditinggalkan disimpan sebagai stale");
+        assert_eq!(chain.is_on_best_chain(&fork_first.hash), Some(true));
+        assert_eq!(chain.is_on_best_chain(&local.hash), Some(false));
+        assert_eq!(chain.is_on_best_chain(&"f".repeat(64)), None);
+
+        let mut forged = child_block(&fork_second, "rival");
+        forged.nonce += 1;
+        assert!(matches!(chain.add_orphan(forged), Err(ChainError::InvalidProofOfWork)));
+        let mut relabeled = child_block(&fork_second, "rival");
+        relabeled.hash = fork_second.hash.clone();
+        assert!(matches!(chain.add_orphan(relabeled), Err(ChainError::InvalidProofOfWork)), "Hash yang tidak sesuai isi blok ditolak");
+    }
+
+    #[test]
+    fn test_orphan_branch_with_inflated_coinbase_is_rejected() {
+        let mut chain = easy_chain("miner");
+        let local = child_block(&chain.blocks[0], "miner");
+        chain.submit_block(local.clone()).unwrap();
+
+        let fork_first = child_block(&chain.blocks[0], "rival");
+        let mut fork_second = Block::new(2, fork_first.hash.clone(), 1, vec![Transaction::coinbase("rival".into(), 1_000_000)]);
+        solve(&mut fork_second);
+        let fork_third = child_block(&fork_second, "rival");
+        assert_eq!(chain.add_orphan(fork_third).unwrap(), 0);
+        assert_eq!(chain.add_orphan(fork_second).unwrap(), 0);
+        assert_eq!(chain.add_orphan(fork_first).unwrap(), 0, "Cabang lebih berat tetap ditolak karena coinbase-nya");
+
+        assert_eq!(chain.blocks.last().unwrap().hash, local.hash);
+        assert_eq!(chain.orphan_count(), 0, "Cabang yang tidak valid dibuang seluruhnya dari pool");
+        assert_eq!(chain.total_supply, 100);
+    }
+
+    #[test]
+    fn test_prune_orphan_pool_evicts_expired_blocks() {
+        let mut chain = easy_chain("miner");
+        let first = child_block(&chain.blocks[0], "miner");
+        let expired = child_block(&first, "miner");
+        let fresh = child_block(&expired, "miner");
+        chain.add_orphan(expired.clone()).unwrap();
+        chain.add_orphan(fresh.clone()).unwrap();
+        chain.orphans[0].received_at = 1_000;
+
+        let now = chain.orphans[1].received_at;
+        assert_eq!(chain.prune_orphan_pool(now, 60_000), 1);
+        assert_eq!(chain.is_on_best_chain(&expired.hash), None);
+        assert_eq!(chain.is_on_best_chain(&fresh.hash), Some(false));
+        assert_eq!(chain.prune_orphan_pool(now + 60_001, 60_000), 1);
+        assert_eq!(chain.orphan_count(), 0);
+    }
+
+    #[test]
+    fn test_fork_tips_lists_competing_branch() {
+        let mut chain = easy_chain("miner");
+        for _ in 0..2 {
+            let block = next_block(&chain, vec![Transaction::coinbase("miner".into(), 50)]);
+            chain.submit_block(block).unwrap();
+        }
+        let fork_first = child_block(&chain.blocks[0], "rival");
+        let fork_second = child_block(&fork_first, "rival");
+        chain.add_orphan(fork_first.clone()).unwrap();
+        chain.add_orphan(fork_second.clone()).unwrap();
+        assert_eq!(chain.blocks.len(), 3, "Kerja sama besar tidak memicu reorg");
+
+        let unit = difficulty_to_expected_hashes(1);
+        assert_eq!(chain.fork_tips(), vec![(fork_second.hash.clone(), 2, 3 * unit)]);
+        let branch = chain.fork_branch(&fork_second.hash).unwrap();
+        assert_eq!(branch, vec![fork_first.header(), fork_second.header()]);
+        assert_eq!(chain.fork_branch(&chain.blocks[2].hash.clone()), None);
+
+        let detached = child_block(&child_block(&fork_second, "rival"), "rival");
+        chain.add_orphan(detached.clone()).unwrap();
+        assert!(chain.fork_tips().contains(&(detached.hash, 4, unit)), "Cabang yang induknya belum tiba hanya menghitung bloknya sendiri");
+    }
+
+    #[test]
+    fn test_get_block_by_hash_and_index() {
+        let mut chain = valid_chain();
+        let genesis_hash = chain.blocks[0].hash.clone();
+        assert_eq!(chain.get_block_by_hash(&genesis_hash).map(|block| block.index), Some(0));
+        assert_eq!(chain.get_block_by_index(2).map(|block| block.hash.clone()), Some(chain.blocks[2].hash.clone()));
+        assert!(chain.get_block_by_hash(&"0".repeat(64)).is_none());
+        assert!(chain.get_block_by_index(3).is_none());
+
+        let old_tip = chain.blocks[2].hash.clone();
+        let mut longer = easy_chain("rival");
+        for _ in 0..4 {
+            let block = next_block(&longer, vec![Transaction::coinbase("rival".into(), 50)]);
+            longer.submit_block(block).unwrap();
+        }
+        assert_eq!(chain.replace_chain(candidate_blocks(&longer)), Ok(true));
+        assert!(chain.get_block_by_hash(&old_tip).is_none(), "Indeks ikut diganti setelah reorg");
+        let tip = &longer.blocks[4].hash;
+        assert_eq!(chain.get_block_by_hash(tip).map(|block| block.index), Some(4));
+        assert_eq!(chain.block_index.get(tip), Some(&4));
+    }
+
+    #[test]
+    fn test_is_valid_accepts_consistent_chain() {
+        assert_eq!(valid_chain().is_valid(), Ok(()));
+    }
+
+    #[test]
+    fn test_is_valid_detects_tampering() {
+        let mut chain = valid_chain();
+        Arc::make_mut(&mut chain.blocks[1]).nonce += 1;
+        assert_eq!(chain.is_valid(), Err(ValidationError::HashMismatch { index: 1 }));
+
+        let mut chain = valid_chain();
+        Arc::make_mut(&mut chain.blocks[2]).previous_hash = "f".repeat(64);
+        assert_eq!(chain.is_valid(), Err(ValidationError::BrokenLink { index: 2 }));
+
+        let mut chain = valid_chain();
+        Arc::make_mut(&mut chain.blocks[1]).transactions[0] = Transaction::coinbase("attacker".into(), 5000);
+        assert_eq!(chain.is_valid(), Err(ValidationError::MerkleMismatch { index: 1 }));
+
+        let mut chain = valid_chain();
+        let block = Arc::make_mut(&mut chain.blocks[2]);
+        block.difficulty = 64;
+        block.hash = block.calculate_hash();
+        assert_eq!(chain.is_valid(), Err(ValidationError::BadProofOfWork { index: 2 }));
+    }
+
+    #[test]
+    fn test_validate_range_checks_only_the_slice() {
+        let mut chain = easy_chain("miner");
+        for _ in 0..9 {
+            let block = next_block(&chain, vec![Transaction::coinbase("miner".into(), 50)]);
+            chain.submit_block(block).unwrap();
+        }
+        assert_eq!(chain.validate_range(5, 10), Ok(()));
+
+        Arc::make_mut(&mut chain.blocks[2]).nonce += 1;
+        assert_eq!(chain.validate_range(5, 10), Ok(()), "Blok di luar rentang tidak diperiksa");
+        assert_eq!(chain.validate_range(0, 10), Err(ValidationError::HashMismatch { index: 2 }));
+        assert_eq!(chain.validate_range(5, 11), Err(ValidationError::RangeOutOfBounds { start: 5, end: 11 }));
+    }
+
+    #[test]
+    fn test_validate_range_rejects_unlinked_start() {
+        let mut chain = easy_chain("miner");
+        for _ in 0..9 {
+            let block = next_block(&chain, vec![Transaction::coinbase("miner".into(), 50)]);
+            chain.submit_block(block).unwrap();
+        }
+        let block = Arc::make_mut(&mut chain.blocks[5]);
+        block.previous_hash = "f".repeat(64);
+        solve(block);
+        assert_eq!(chain.validate_range(5, 10), Err(ValidationError::BrokenLink { index: 5 }));
+    }
+
+    #[test]
+    fn test_rejects_backwards_timestamp() {
+        let mut chain = valid_chain();
+        let previous = chain.blocks[1].timestamp;
+        let block = Arc::make_mut(&mut chain.blocks[2]);
+        block.timestamp = previous - 1;
+        solve(block);
+        assert_eq!(chain.is_valid(), Err(ValidationError::TimestampRegression { index: 2 }));
+
+        let mut chain = valid_chain();
+        let tip = chain.blocks.last().unwrap();
+        let mut block = Block::new(tip.index + 1, tip.hash.clone(), 1, vec![Transaction::coinbase("miner".into(), 50)]);
+        block.timestamp = tip.timestamp - 1;
+        solve(&mut block);
+        assert!(matches!(
+            chain.submit_block(block),
+            Err(ChainError::Validation(ValidationError::TimestampRegression { index: 3 }))
+        ));
+    }
+
+    #[test]
+    fn test_is_valid_requires_zero_genesis_link() {
+        let mut chain = easy_chain("miner");
+        let genesis = Arc::make_mut(&mut chain.blocks[0]);
+        genesis.previous_hash = "1".repeat(64);
+        solve(genesis);
+        assert_eq!(chain.is_valid(), Err(ValidationError::BrokenLink { index: 0 }));
+    }
+
+    #[test]
+    fn test_save_and_load_round_trip() {
+        let miner = signing_key(1);
+        let mut chain = easy_chain(&address(&miner));
+        for _ in 0..3 {
+            let block = next_block(&chain, vec![Transaction::coinbase(address(&miner), 50)]);
+            chain.submit_block(block).unwrap();
+        }
+        chain.add_transaction(signed_tx(&miner, "alice", 5)).unwrap();
+
+        let path = std::env::temp_dir().join(format!("relogicchain-chain-{}.json", std::process::id()));
+        chain.save_to_file(&path).unwrap();
+        let loaded = Blockchain::load_from_file(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let hashes = |chain: &Blockchain| chain.blocks.iter().map(|block| block.hash.clone()).collect::<Vec<_>>();
+        assert_eq!(hashes(&loaded), hashes(&chain));
+        assert_eq!(loaded.total_supply, chain.total_supply);
+        assert_eq!(loaded.params(), chain.params());
+        assert_eq!(loaded.pending_transactions, chain.pending_transactions);
+    }
+
+    #[test]
+    fn test_bincode_round_trip() {
+        let mut chain = easy_chain("miner");
+        for _ in 0..5 {
+            let block = next_block(&chain, vec![Transaction::coinbase("miner".into(), 50)]);
+            chain.submit_block(block).unwrap();
+        }
+
+        let path = std::env::temp_dir().join(format!("relogicchain-chain-{}.bin", std::process::id()));
+        chain.save_bincode(&path).unwrap();
+        let loaded = Blockchain::load_bincode(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded.blocks.len(), 6);
+        assert_eq!(loaded.is_valid(), Ok(()));
+        assert_eq!(loaded.blocks.last().unwrap().hash, chain.blocks.last().unwrap().hash);
+        assert_eq!(loaded.total_supply, chain.total_supply);
+    }
+
+    #[test]
+    fn test_load_bincode_rejects_truncated_file() {
+        let chain = easy_chain("miner");
+        let path = std::env::temp_dir().join(format!("relogicchain-truncated-{}.bin", std::process::id()));
+        chain.save_bincode(&path).unwrap();
+        let bytes = std::fs::read(&path).unwrap();
+        std::fs::write(&path, &bytes[..bytes.len() / 2]).unwrap();
+
+        let result = Blockchain::load_bincode(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert!(matches!(result, Err(StorageError::Bincode(_))));
+    }
+
+    #[test]
+    fn test_load_rederives_total_supply() {
+        let chain = easy_chain("miner");
+        let path = std::env::temp_dir().join(format!("relogicchain-supply-{}.json", std::process::id()));
+        chain.save_to_file(&path).unwrap();
+        let mut json: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        json["total_supply"] = serde_json::json!(1_000_000);
+        std::fs::write(&path, json.to_string()).unwrap();
+
+        let loaded = Blockchain::load_from_file(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(loaded.total_supply, INITIAL_REWARD);
+    }
+
+    #[test]
+    fn test_pending_by_sender() {
+        let (miner, alice) = (signing_key(1), signing_key(2));
+        let mut chain = test_chain(&address(&miner));
+        let first = signed_tx(&miner, &address(&alice), 20);
+        let second = signed_tx(&miner, "bob", 5);
+        let other = signed_tx(&alice, "bob", 5);
+        for tx in [first.clone(), other, second.clone()] {
+            chain.add_transaction(tx).unwrap();
+        }
+
+        assert_eq!(chain.pending_by_sender(&address(&miner)), vec![&first, &second]);
+        assert_eq!(chain.pending_by_sender(&address(&alice)).len(), 1);
+        assert!(chain.pending_by_sender("bob").is_empty());
+    }
+
+    #[test]
+    fn test_apply_external_mempool_skips_invalid_transactions() {
+        let (miner, alice) = (signing_key(1), signing_key(2));
+        let mut chain = test_chain(&address(&miner));
+        let local = signed_tx(&miner, &address(&alice), 10);
+        chain.add_transaction(local.clone()).unwrap();
+
+        let batch = vec![
+            local,
+            signed_tx(&miner, "bob", 10),
+            signed_tx(&signing_key(3), "bob", 10),
+            signed_tx(&alice, "carol", 5),
+        ];
+        let report = chain.apply_external_mempool(batch);
+        assert_eq!(report, MempoolSyncReport { accepted: 2, rejected: 1, duplicate: 1 });
+        assert_eq!(chain.pending_transactions.len(), 3);
+    }
+
+    #[test]
+    fn test_compact_mempool_keeps_only_valid_transactions() {
+        let miner = signing_key(1);
+        let mut chain = easy_chain(&address(&miner));
+        let confirmed = signed_tx(&miner, "alice", 10);
+        let block = next_block(&chain, vec![Transaction::coinbase(address(&miner), 50), confirmed.clone()]);
+        chain.submit_block(block).unwrap();
+
+        let valid = signed_tx(&miner, "bob", 5);
+        let overspend = signed_tx(&signing_key(3), "bob", 5);
+        let high_fee = signed_tx_with_fee(&miner, "carol", 5, 3);
+        let same_fee = signed_tx(&miner, "dave", 5);
+        chain.pending_transactions = vec![valid.clone(), valid.clone(), confirmed, overspend, high_fee.clone(), same_fee.clone()];
+        let events = chain.subscribe_mempool();
+
+        let summary = chain.compact_mempool();
+        assert_eq!(summary, MempoolCompaction { duplicates: 1, confirmed: 1, invalid: 1 });
+        assert_eq!(chain.pending_transactions, vec![high_fee, valid, same_fee], "Fee tertinggi dulu, fee sama sesuai kedatangan");
+        assert_eq!(events.try_iter().count(), 2);
+    }
+
+    #[test]
+    fn test_compact_mempool_keeps_dependents_behind_their_parent() {
+        let (miner, alice) = (signing_key(1), signing_key(2));
+        let mut chain = easy_chain(&address(&miner));
+        let fund_alice = signed_tx_with_fee(&miner, &address(&alice), 10, 1);
+        let alice_pays = signed_tx_with_fee(&alice, "bob", 5, 4);
+        chain.add_transaction(fund_alice.clone()).unwrap();
+        chain.add_transaction(alice_pays.clone()).unwrap();
+
+        for _ in 0..2 {
+            assert_eq!(chain.compact_mempool(), MempoolCompaction::default());
+            assert_eq!(chain.pending_transactions, vec![fund_alice.clone(), alice_pays.clone()], "Fee lebih tinggi tetap menunggu induknya");
+        }
+    }
+
+    #[test]
+    fn test_no_progress_env_suppresses_bar() {
+        std::env::set_var(NO_PROGRESS_ENV, "1");
+        assert!(env_flag_enabled(std::env::var_os(NO_PROGRESS_ENV)));
+        std::env::remove_var(NO_PROGRESS_ENV);
+
+        assert!(!env_flag_enabled(None));
+        assert!(!env_flag_enabled(Some("".into())));
+        assert!(!env_flag_enabled(Some("0".into())));
+        assert!(mining_progress_bar(true).is_hidden());
+    }
+
+    #[test]
+    fn test_get_block_with_proof_links_to_tip() {
+        let mut chain = easy_chain("miner");
+        for _ in 0..4 {
+            let block = next_block(&chain, vec![Transaction::coinbase("miner".into(), 50)]);
+            chain.submit_block(block).unwrap();
+        }
+
+        let (block, headers) = chain.get_block_with_proof(1).unwrap();
+        assert_eq!(block.index, 1);
+        assert_eq!(headers.first().unwrap().hash, block.hash);
+        assert_eq!(headers.last().unwrap().hash, chain.blocks.last().unwrap().hash);
+        assert_eq!(headers.len(), 4);
+        for pair in headers.windows(2) {
+            assert_eq!(pair[1].previous_hash, pair[0].hash);
+            assert_eq!(pair[1].calculate_hash(), pair[1].hash);
+        }
+        assert!(chain.get_block_with_proof(5).is_none());
+    }
+
+    #[test]
+    fn test_transactions_in_block_separates_coinbase() {
+        let miner = signing_key(1);
+        let mut chain = easy_chain(&address(&miner));
+        let transfers = vec![signed_tx(&miner, "alice", 10), signed_tx(&miner, "bob", 5)];
+        let mut transactions = vec![Transaction::coinbase(address(&miner), 50)];
+        transactions.extend(transfers.clone());
+        let block = next_block(&chain, transactions);
+        chain.submit_block(block).unwrap();
+
+        let (coinbase, rest) = chain.transactions_in_block(1).unwrap();
+        assert!(coinbase.is_coinbase());
+        assert_eq!(coinbase.amount, 50);
+        assert_eq!(rest, transfers.as_slice());
+        assert!(chain.transactions_in_block(2).is_none());
+    }
+
+    #[test]
+    fn test_compact_chain_summary_detects_divergence() {
+        let mut node_a = easy_chain("miner");
+        let block = next_block(&node_a, vec![Transaction::coinbase("miner".into(), 50)]);
+        node_a.submit_block(block).unwrap();
+
+        let mut node_b = test_chain_with("miner", easy_params());
+        node_b.blocks = node_a.blocks.clone();
+        assert_eq!(node_a.compact_chain_summary(), node_b.compact_chain_summary());
+        assert_eq!(node_a.compact_chain_summary().total_work, 2 * difficulty_to_expected_hashes(1));
+
+        let block = next_block(&node_a, vec![Transaction::coinbase("miner".into(), 50)]);
+        node_a.submit_block(block).unwrap();
+        let (summary_a, summary_b) = (node_a.compact_chain_summary(), node_b.compact_chain_summary());
+        assert_ne!(summary_a, summary_b);
+        assert_eq!(summary_a.genesis_hash, summary_b.genesis_hash);
+        assert_eq!(summary_a.height, summary_b.height + 1);
+        assert!(summary_a.total_work > summary_b.total_work);
+    }
+
+    #[test]
+    fn test_summary_of_fresh_chain() {
+        let chain = easy_chain("miner");
+        let summary = chain.summary();
+        assert_eq!(summary.height, 1);
+        assert_eq!(summary.tip_hash, chain.blocks[0].hash);
+        assert_eq!(summary.difficulty, 1);
+        assert_eq!(summary.total_supply, chain.get_reward(0));
+        assert_eq!(summary.mempool_size, 0);
+        assert_eq!(summary.total_work, difficulty_to_expected_hashes(1));
+        assert!(serde_json::to_string(&summary).unwrap().contains("\"total_supply\":50"));
+    }
+
+    #[test]
+    fn test_recent_blocks_returns_tail_in_order() {
+        let mut chain = easy_chain("miner");
+        for _ in 0..4 {
+            let block = next_block(&chain, vec![Transaction::coinbase("miner".into(), 50)]);
+            chain.submit_block(block).unwrap();
+        }
+
+        let recent: Vec<u64> = chain.recent_blocks(2).iter().map(|block| block.index).collect();
+        assert_eq!(recent, vec![3, 4]);
+        assert_eq!(chain.recent_blocks(100).len(), 5);
+        assert!(chain.recent_blocks(0).is_empty());
+    }
+
+    #[test]
+    fn test_snapshot_is_isolated_from_later_blocks() {
+        let mut chain = easy_chain("miner");
+        let block = next_block(&chain, vec![Transaction::coinbase("miner".into(), 50)]);
+        chain.submit_block(block).unwrap();
+
+        let snapshot = chain.snapshot();
+        let tip_hash = chain.blocks.last().unwrap().hash.clone();
+        assert!(Arc::ptr_eq(&snapshot.blocks()[1], &chain.blocks[1]), "Snapshot tidak boleh menyalin blok");
+
+        for _ in 0..2 {
+            let block = next_block(&chain, vec![Transaction::coinbase("miner".into(), 50)]);
+            chain.submit_block(block).unwrap();
+        }
+
+        assert_eq!(chain.blocks.len(), 4);
+        assert_eq!(snapshot.height(), 2);
+        assert_eq!(snapshot.tip().hash, tip_hash);
+        assert_eq!(snapshot.headers().len(), 2);
+    }
+
+    #[test]
+    fn test_audit_difficulty_reports_mismatched_block() {
+        let mut chain = test_chain("test");
+        for i in 1..5 {
+            let mut block = Block::clone(chain.blocks.last().unwrap());
+            block.index = i;
+            block.timestamp += BLOCK_TIME_SECONDS * 1000;
+            if i == 4 {
+                block.difficulty = INITIAL_DIFFICULTY + 3;
+            }
+            chain.append_block_unchecked(block);
+        }
+
+        assert_eq!(chain.audit_difficulty(), vec![(4, INITIAL_DIFFICULTY + 3, INITIAL_DIFFICULTY)]);
+        assert_eq!(chain.blocks[4].difficulty, INITIAL_DIFFICULTY + 3, "Audit tidak boleh mengubah blok");
+    }
+
+    /// Menambahkan blok-blok instan (timestamp sama) dengan difficulty hasil retarget.
+    fn push_instant_blocks(chain: &mut Blockchain, count: u64) {
+        for _ in 0..count {
+            let mut block = Block::clone(chain.blocks.last().unwrap());
+            block.index += 1;
+            block.difficulty = chain.adjust_difficulty().unwrap();
+            chain.append_block_unchecked(block);
+        }
+    }
+
+    #[test]
+    fn test_difficulty_rate_limit_caps_cumulative_increase() {
+        let limit = DifficultyRateLimit { window_retargets: 1, max_factor: 2.0 };
+        let config = ConsensusParams { initial_difficulty: 4, difficulty_rate_limit: Some(limit), ..ConsensusParams::default() };
+        let mut limited = test_chain_with("test", config);
+        push_instant_blocks(&mut limited, 30);
+
+        let mut unlimited = test_chain_with("test", ConsensusParams { initial_difficulty: 4, ..ConsensusParams::default() });
+        push_instant_blocks(&mut unlimited, 30);
+
+        let interval = DIFFICULTY_ADJUSTMENT_INTERVAL as usize;
+        for height in interval..limited.blocks.len() {
+            let current = limited.blocks[height].difficulty;
+            let window_start = limited.blocks[height - interval].difficulty;
+            assert!(current <= window_start * 2, "Tinggi {}: {} > 2 x {}", height, current, window_start);
+        }
+        assert!(limited.blocks.last().unwrap().difficulty > 4, "Difficulty tetap boleh naik");
+        assert!(limited.blocks.last().unwrap().difficulty < unlimited.blocks.last().unwrap().difficulty);
+    }
+
+    #[test]
+    fn test_adjust_difficulty_respects_max_bound() {
+        let params = ConsensusParams { initial_difficulty: 4, max_difficulty: 10, ..ConsensusParams::default() };
+        let mut chain = test_chain_with("test", params);
+        push_instant_blocks(&mut chain, 30);
+        assert!(chain.blocks.iter().all(|block| block.difficulty <= 10));
+        assert_eq!(chain.adjust_difficulty().unwrap(), 10);
+    }
+
+    #[test]
+    fn test_adjust_difficulty_respects_min_bound() {
+        let params = ConsensusParams { initial_difficulty: 8, min_difficulty: 6, ..ConsensusParams::default() };
+        let mut chain = test_chain_with("test", params);
+        for _ in 0..30 {
+            let mut block = Block::clone(chain.blocks.last().unwrap());
+            block.index += 1;
+            block.timestamp += 60_000;
+            block.difficulty = chain.adjust_difficulty().unwrap();
+            chain.append_block_unchecked(block);
+        }
+        assert_eq!(chain.adjust_difficulty().unwrap(), 6);
+    }
+
+    #[test]
+    fn test_config_rejects_invalid_difficulty_bounds() {
+        for (min, max) in [(0, 10), (12, 10), (1, MAX_DIFFICULTY + 1)] {
+            let config = ConsensusParams { min_difficulty: min, max_difficulty: max, ..ConsensusParams::default() };
+            assert!(matches!(config.validate(), Err(ConfigError::InvalidDifficultyBounds { .. })), "{}..{}", min, max);
+        }
+    }
+
+    fn cli_args(args: &[&str]) -> Vec<String> {
+        std::iter::once("blockchain").chain(args.iter().copied()).map(String::from).collect()
+    }
+
+    #[test]
+    fn test_initial_difficulty_flag_flows_into_genesis() {
+        let params = params_from_args(cli_args(&["--initial-difficulty", "3"])).unwrap();
+        assert_eq!(params.initial_difficulty, 3);
+        let chain = Blockchain::new("miner".into(), params).unwrap();
+        assert_eq!(chain.blocks[0].difficulty, 3);
+        assert!(hash_meets_difficulty(&chain.blocks[0].hash, 3), "Tiga bit teratas hash genesis harus nol");
+        assert_eq!(params_from_args(cli_args(&[])).unwrap(), ConsensusParams::default());
+    }
+
+    #[test]
+    fn test_peers_flag_parses_address_list() {
+        let peers = peers_from_args(cli_args(&["--peers", "127.0.0.1:9000, 127.0.0.1:9001"])).unwrap();
+        assert_eq!(peers, vec!["127.0.0.1:9000".parse().unwrap(), "127.0.0.1:9001".parse::<SocketAddr>().unwrap()]);
+        assert!(peers_from_args(cli_args(&[])).unwrap().is_empty());
+        assert!(matches!(
+            peers_from_args(cli_args(&["--peers", "localhost"])),
+            Err(ConfigError::InvalidArgument { flag: "--peers", .. })
+        ));
+    }
+
+    #[test]
+    fn test_initial_difficulty_flag_is_validated() {
+        assert!(matches!(
+            params_from_args(cli_args(&["--initial-difficulty", "abc"])),
+            Err(ConfigError::InvalidArgument { flag: "--initial-difficulty", .. })
+        ));
+        assert!(matches!(
+            params_from_args(cli_args(&["--initial-difficulty", "0"])),
+            Err(ConfigError::InitialDifficultyOutOfBounds { difficulty: 0, min: 1, max: MAX_ADJUSTED_DIFFICULTY })
+        ));
+        assert!(matches!(
+            params_from_args(cli_args(&["--initial-difficulty", "65"])),
+            Err(ConfigError::InitialDifficultyOutOfBounds { difficulty: 65, .. })
+        ));
+    }
+
+    #[test]
+    fn test_config_rejects_invalid_rate_limit() {
+        let limit = DifficultyRateLimit { window_retargets: 2, max_factor: 0.5 };
+        let config = ConsensusParams { difficulty_rate_limit: Some(limit), ..ConsensusParams::default() };
+        assert!(matches!(config.validate(), Err(ConfigError::InvalidDifficultyRateLimit)));
+    }
+
+    #[test]
+    fn test_retarget_rejects_inconsistent_tip() {
+        let mut chain = test_chain("test");
+        let mut block = Block::clone(chain.blocks.last().unwrap());
+        block.index = 5;
+        chain.append_block_unchecked(block);
+
+        assert!(matches!(
+            chain.adjust_difficulty(),
+            Err(ChainError::InconsistentTip { height: 2, tip_index: 5 })
+        ));
+        assert!(matches!(chain.mine_and_add_block(), Err(ChainError::InconsistentTip { .. })));
     }
 
     #[test]
     fn test_difficulty_adjustment_increase() {
-        let mut chain = Blockchain::new("test".into());
+        let mut chain = test_chain("test");
         let initial_difficulty = chain.blocks[0].difficulty;
         
         // Simulasikan blok yang sangat cepat
         for i in 1..=DIFFICULTY_ADJUSTMENT_INTERVAL {
-            let mut last_block = chain.blocks.last().unwrap().clone();
+            let mut last_block = Block::clone(chain.blocks.last().unwrap());
             last_block.index = i;
             // Kurangi 5 detik dari timestamp sebelumnya
-            last_block.timestamp = last_block.timestamp - 5000; 
-            chain.blocks.push(last_block);
+            last_block.timestamp -= 5000;
+            chain.blocks.push(Arc::new(last_block));
         }
         
-        let new_difficulty = chain.adjust_difficulty();
+        let new_difficulty = chain.adjust_difficulty().unwrap();
         assert!(new_difficulty > initial_difficulty, "Kesulitan seharusnya meningkat");
     }
 
     #[test]
     fn test_difficulty_adjustment_decrease() {
-        let mut chain = Blockchain::new("test".into());
+        let mut chain = test_chain("test");
         let initial_difficulty = chain.blocks[0].difficulty;
         
         // Simulasikan blok yang sangat lambat
         for i in 1..=DIFFICULTY_ADJUSTMENT_INTERVAL {
-            let mut last_block = chain.blocks.last().unwrap().clone();
+            let mut last_block = Block::clone(chain.blocks.last().unwrap());
             last_block.index = i;
             // Tambah 20 detik dari timestamp sebelumnya
-            last_block.timestamp = last_block.timestamp + 20000; 
-            chain.blocks.push(last_block);
+            last_block.timestamp += 20000;
+            chain.blocks.push(Arc::new(last_block));
         }
         
-        let new_difficulty = chain.adjust_difficulty();
+        let new_difficulty = chain.adjust_difficulty().unwrap();
         assert!(new_difficulty < initial_difficulty, "Kesulitan seharusnya menurun");
     }
 }
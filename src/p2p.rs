@@ -0,0 +1,347 @@
+//! Jaringan P2P minimal di atas TCP: setiap pesan adalah satu baris JSON.
+//!
+//! Node mendengarkan di satu port dan menyimpan daftar alamat peer yang diberikan saat startup.
+//! Blok yang ditambang dikirim ke semua peer sebagai `Message::NewBlock`; blok yang diterima
+//! diperiksa dengan `Blockchain::verify_block` lalu ditambahkan jika valid. Blok tidak
+//! diteruskan ulang, jadi setiap node perlu mengenal peer-nya secara langsung.
+//!
+//! Node yang tertinggal mengejar lewat `Message::GetBlocks`, yang dijawab peer dengan
+//! `Message::Blocks` pada koneksi yang sama. Jika blok peer tidak tertaut ke tip lokal
+//! (fork atau genesis berbeda), seluruh rantai peer diminta dan diserahkan ke `replace_chain`,
+//! yang memeriksa ulang setiap bloknya dari genesis dengan aturan `verify_block`.
+//! Blok siaran yang tidak tertaut disimpan di orphan pool rantai sampai induknya tiba.
+
+use crate::{Block, ChainError, SharedChain, ValidationError};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+/// Batas waktu koneksi ke peer agar peer yang mati tidak menahan broadcast.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(2);
+/// Batas waktu menunggu balasan `GetBlocks`.
+const RESPONSE_TIMEOUT: Duration = Duration::from_secs(10);
+/// Orphan yang induknya tidak tiba dalam rentang ini dibuang sebelum orphan baru disimpan.
+const ORPHAN_TTL: Duration = Duration::from_secs(600);
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type")]
+pub enum Message {
+    NewBlock { block: Block },
+    GetBlocks { from_index: u64 },
+    Blocks { blocks: Vec<Block> },
+}
+
+/// Hasil `Node::sync_from`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SyncOutcome {
+    /// Blok peer ditambahkan di atas tip lokal.
+    Extended(usize),
+    /// Rantai lokal diganti rantai peer yang kerjanya lebih besar.
+    Reorganized,
+    /// Peer tidak punya blok baru, atau rantainya tidak lebih berat.
+    Unchanged,
+}
+
+/// Alasan blok dari peer tidak ditambahkan.
+#[derive(Debug)]
+pub enum PeerError {
+    Io(io::Error),
+    Json(serde_json::Error),
+    Validation(ValidationError),
+    Chain(ChainError),
+    /// Peer membalas dengan jenis pesan yang tidak diharapkan.
+    UnexpectedMessage,
+}
+
+#[derive(Debug, Clone)]
+pub struct Node {
+    chain: SharedChain,
+    peers: Vec<SocketAddr>,
+}
+
+impl Node {
+    pub fn new(chain: SharedChain, peers: Vec<SocketAddr>) -> Self {
+        Node { chain, peers }
+    }
+
+    pub fn chain(&self) -> &SharedChain {
+        &self.chain
+    }
+
+    /// Mendengarkan koneksi peer di `addr` pada thread terpisah; mengembalikan alamat yang
+    /// benar-benar di-bind (berguna untuk port `0`). Setiap koneksi dilayani thread-nya sendiri.
+    pub fn listen(&self, addr: &str) -> io::Result<(SocketAddr, JoinHandle<()>)> {
+        let listener = TcpListener::bind(addr)?;
+        let local_addr = listener.local_addr()?;
+        let node = self.clone();
+        let handle = thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let node = node.clone();
+                thread::spawn(move || node.serve_connection(stream));
+            }
+        });
+        Ok((local_addr, handle))
+    }
+
+    fn serve_connection(&self, stream: TcpStream) {
+        let Ok(mut writer) = stream.try_clone() else { return };
+        for line in BufReader::new(stream).lines() {
+            let Ok(line) = line else { return };
+            if line.trim().is_empty() {
+                continue;
+            }
+            match self.handle_line(&line) {
+                Ok(Some(reply)) => {
+                    if write_message(&mut writer, &reply).is_err() {
+                        return;
+                    }
+                }
+                Ok(None) => {}
+                Err(err) => println!("Pesan dari peer ditolak: {:?}", err),
+            }
+        }
+    }
+
+    /// Memproses satu pesan; `Some` berisi balasan untuk dikirim di koneksi yang sama.
+    fn handle_line(&self, line: &str) -> Result<Option<Message>, PeerError> {
+        match serde_json::from_str(line).map_err(PeerError::Json)? {
+            Message::NewBlock { block } => {
+                let index = block.index;
+                match self.accept_block(block.clone()) {
+                    // Blok yang tidak tertaut ke tip berarti node ini tertinggal atau berada di fork lain.
+                    // Blok disimpan sebagai orphan; jika masih tertinggal, sisanya dikejar dari peer.
+                    Err(PeerError::Validation(ValidationError::BrokenLink { .. } | ValidationError::IndexMismatch { .. })) => {
+                        let mut chain = self.chain.write();
+                        chain.prune_orphan_pool(Utc::now().timestamp_millis() as u64, ORPHAN_TTL.as_millis() as u64);
+                        chain.add_orphan(block).map_err(PeerError::Chain)?;
+                        let behind = index >= chain.blocks.len() as u64;
+                        drop(chain);
+                        if behind {
+                            self.sync_with_peers();
+                        }
+                        Ok(None)
+                    }
+                    result => result.map(|_| None),
+                }
+            }
+            Message::GetBlocks { from_index } => {
+                let chain = self.chain.read();
+                let start = usize::try_from(from_index).unwrap_or(usize::MAX).min(chain.blocks.len());
+                let blocks = chain.blocks[start..].iter().map(|block| Block::clone(block)).collect();
+                Ok(Some(Message::Blocks { blocks }))
+            }
+            Message::Blocks { .. } => Err(PeerError::UnexpectedMessage),
+        }
+    }
+
+    /// Memverifikasi blok dari peer lalu menambahkannya ke rantai, kemudian mencoba menautkan
+    /// orphan yang menunggu blok ini. Lock tulis dipegang selama verifikasi agar tip tidak berubah
+    /// di antara pemeriksaan dan penambahan.
+    pub fn accept_block(&self, block: Block) -> Result<(), PeerError> {
+        let mut chain = self.chain.write();
+        if chain.contains_block(&block.hash) {
+            return Ok(());
+        }
+        chain.verify_block(&block).map_err(PeerError::Validation)?;
+        chain.submit_block(block).map_err(PeerError::Chain)?;
+        chain.connect_orphans();
+        Ok(())
+    }
+
+    /// Mengejar rantai `peer`: blok setelah tip lokal diminta lalu diverifikasi dan ditambahkan
+    /// berurutan. Jika blok pertama tidak tertaut (fork atau genesis berbeda), seluruh rantai peer
+    /// diminta dan diadopsi lewat `replace_chain` bila kerjanya lebih besar.
+    pub fn sync_from(&self, peer: &SocketAddr) -> Result<SyncOutcome, PeerError> {
+        let height = self.chain.read().blocks.len() as u64;
+        let blocks = request_blocks(peer, height)?;
+        let Some(first) = blocks.first() else {
+            return Ok(SyncOutcome::Unchanged);
+        };
+        if first.previous_hash == self.chain.read().blocks.last().unwrap().hash {
+            let count = blocks.len();
+            for block in blocks {
+                self.accept_block(block)?;
+            }
+            return Ok(SyncOutcome::Extended(count));
+        }
+
+        let candidate = request_blocks(peer, 0)?;
+        match self.chain.write().replace_chain(candidate) {
+            Ok(true) => Ok(SyncOutcome::Reorganized),
+            Ok(false) => Ok(SyncOutcome::Unchanged),
+            Err(err) => Err(PeerError::Validation(err)),
+        }
+    }
+
+    /// `sync_from` ke setiap peer yang dikenal; peer yang gagal hanya dicatat.
+    pub fn sync_with_peers(&self) {
+        for peer in &self.peers {
+            match self.sync_from(peer) {
+                Ok(SyncOutcome::Unchanged) => {}
+                Ok(outcome) => println!("Sinkronisasi dengan {}: {:?}", peer, outcome),
+                Err(err) => println!("Sinkronisasi dengan {} gagal: {:?}", peer, err),
+            }
+        }
+    }
+
+    /// Mengirim blok ke semua peer; mengembalikan jumlah peer yang berhasil dihubungi.
+    /// Peer yang tidak bisa dihubungi dilewati.
+    pub fn broadcast(&self, block: &Block) -> usize {
+        let message = Message::NewBlock { block: block.clone() };
+        self.peers.iter().filter(|peer| send(peer, &message).is_ok()).count()
+    }
+
+    /// Menambang satu blok lewat `SharedChain::mine_one` lalu menyiarkannya ke peer.
+    pub fn mine_and_broadcast(&self) -> Result<Arc<Block>, ChainError> {
+        let block = self.chain.mine_one()?;
+        self.broadcast(&block);
+        Ok(block)
+    }
+}
+
+fn send(peer: &SocketAddr, message: &Message) -> io::Result<()> {
+    let mut stream = TcpStream::connect_timeout(peer, CONNECT_TIMEOUT)?;
+    write_message(&mut stream, message)
+}
+
+fn write_message(stream: &mut TcpStream, message: &Message) -> io::Result<()> {
+    let mut line = serde_json::to_string(message).map_err(io::Error::other)?;
+    line.push('\n');
+    stream.write_all(line.as_bytes())
+}
+
+/// Meminta blok mulai `from_index` ke `peer` dan menunggu balasan `Message::Blocks`.
+fn request_blocks(peer: &SocketAddr, from_index: u64) -> Result<Vec<Block>, PeerError> {
+    let mut stream = TcpStream::connect_timeout(peer, CONNECT_TIMEOUT).map_err(PeerError::Io)?;
+    stream.set_read_timeout(Some(RESPONSE_TIMEOUT)).map_err(PeerError::Io)?;
+    write_message(&mut stream, &Message::GetBlocks { from_index }).map_err(PeerError::Io)?;
+    let mut line = String::new();
+    BufReader::new(stream).read_line(&mut line).map_err(PeerError::Io)?;
+    match serde_json::from_str(&line).map_err(PeerError::Json)? {
+        Message::Blocks { blocks } => Ok(blocks),
+        _ => Err(PeerError::UnexpectedMessage),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{mine_block_range, Blockchain, ConsensusParams, Transaction};
+    use std::time::Instant;
+
+    fn params() -> ConsensusParams {
+        ConsensusParams { initial_difficulty: 1, ..ConsensusParams::default() }
+    }
+
+    /// Dua rantai yang berbagi genesis yang sama, agar blok dari satu node tertaut di node lain.
+    fn twin_chains() -> (SharedChain, SharedChain) {
+        let genesis = Block::clone(&Blockchain::new("miner".into(), params()).unwrap().blocks[0]);
+        let a = Blockchain::with_genesis(genesis.clone(), params()).unwrap();
+        let b = Blockchain::with_genesis(genesis, params()).unwrap();
+        (SharedChain::new(a), SharedChain::new(b))
+    }
+
+    fn wait_for_height(chain: &SharedChain, height: usize) -> bool {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while Instant::now() < deadline {
+            if chain.read().blocks.len() >= height {
+                return true;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        false
+    }
+
+    #[test]
+    fn test_block_mined_on_a_appears_on_b() {
+        let (chain_a, chain_b) = twin_chains();
+        let node_b = Node::new(chain_b.clone(), Vec::new());
+        let (addr_b, _handle) = node_b.listen("127.0.0.1:0").unwrap();
+        let node_a = Node::new(chain_a, vec![addr_b]);
+
+        let block = node_a.mine_and_broadcast().unwrap();
+        assert!(wait_for_height(&chain_b, 2), "Blok tidak sampai ke node B");
+        assert_eq!(chain_b.read().blocks[1].hash, block.hash);
+    }
+
+    #[test]
+    fn test_accept_block_rejects_invalid_block() {
+        let (chain_a, chain_b) = twin_chains();
+        let block = chain_a.mine_one().unwrap();
+        let mut forged = Block::clone(&block);
+        forged.nonce += 1;
+
+        let node_b = Node::new(chain_b.clone(), Vec::new());
+        assert!(matches!(node_b.accept_block(forged), Err(PeerError::Validation(ValidationError::HashMismatch { index: 1 }))));
+        assert!(node_b.accept_block(Block::clone(&block)).is_ok());
+        assert!(node_b.accept_block(Block::clone(&block)).is_ok(), "Blok yang sudah dimiliki diabaikan");
+        assert_eq!(chain_b.read().blocks.len(), 2);
+    }
+
+    #[test]
+    fn test_sync_catches_up_on_shared_genesis() {
+        let (chain_a, chain_b) = twin_chains();
+        for _ in 0..5 {
+            chain_a.mine_one().unwrap();
+        }
+        let (addr_a, _handle) = Node::new(chain_a.clone(), Vec::new()).listen("127.0.0.1:0").unwrap();
+
+        let node_b = Node::new(chain_b.clone(), vec![addr_a]);
+        assert_eq!(node_b.sync_from(&addr_a).unwrap(), SyncOutcome::Extended(5));
+        assert_eq!(chain_b.read().blocks.last().unwrap().hash, chain_a.read().blocks.last().unwrap().hash);
+        assert_eq!(node_b.sync_from(&addr_a).unwrap(), SyncOutcome::Unchanged);
+    }
+
+    #[test]
+    fn test_sync_from_fresh_node_reorganizes_onto_heavier_chain() {
+        let chain_a = SharedChain::new(Blockchain::new("miner-a".into(), params()).unwrap());
+        for _ in 0..5 {
+            chain_a.mine_one().unwrap();
+        }
+        let (addr_a, _handle) = Node::new(chain_a.clone(), Vec::new()).listen("127.0.0.1:0").unwrap();
+
+        // Node B baru dimulai: hanya punya genesis miliknya sendiri.
+        let chain_b = SharedChain::new(Blockchain::new("miner-b".into(), params()).unwrap());
+        let node_b = Node::new(chain_b.clone(), vec![addr_a]);
+        assert_eq!(node_b.sync_from(&addr_a).unwrap(), SyncOutcome::Reorganized);
+
+        let (a, b) = (chain_a.read(), chain_b.read());
+        assert_eq!(b.blocks.len(), 6);
+        assert!(a.blocks.iter().zip(b.blocks.iter()).all(|(x, y)| x.hash == y.hash));
+        assert_eq!(b.is_valid(), Ok(()));
+    }
+
+    #[test]
+    fn test_sync_from_rejects_heavier_chain_with_inflated_coinbase() {
+        let chain_a = SharedChain::new(Blockchain::new("miner-a".into(), params()).unwrap());
+        chain_a.mine_one().unwrap();
+        {
+            let mut a = chain_a.write();
+            for _ in 0..4 {
+                let tip = a.blocks.last().unwrap();
+                let mut block = Block::new(tip.index + 1, tip.hash.clone(), 1, vec![Transaction::coinbase("miner-a".into(), 1_000_000)]);
+                mine_block_range(&mut block, 1, 0..u64::MAX).unwrap();
+                a.append_block_unchecked(block);
+            }
+        }
+        let (addr_a, _handle) = Node::new(chain_a.clone(), Vec::new()).listen("127.0.0.1:0").unwrap();
+
+        let chain_b = SharedChain::new(Blockchain::new("miner-b".into(), params()).unwrap());
+        let node_b = Node::new(chain_b.clone(), vec![addr_a]);
+        assert!(matches!(node_b.sync_from(&addr_a), Err(PeerError::Validation(ValidationError::InvalidCoinbase { index: 2 }))));
+        assert_eq!(chain_b.read().blocks.len(), 1);
+    }
+
+    #[test]
+    fn test_broadcast_skips_unreachable_peers() {
+        let (chain_a, _) = twin_chains();
+        let unused = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap();
+        let node = Node::new(chain_a.clone(), vec![unused]);
+        let tip = Block::clone(chain_a.read().blocks.last().unwrap());
+        assert_eq!(node.broadcast(&tip), 0);
+    }
+}
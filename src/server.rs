@@ -0,0 +1,146 @@
+//! Server JSON sederhana di atas `tiny_http` yang berbagi `SharedChain` dengan loop mining.
+//!
+//! Endpoint:
+//! - `GET /stats` — ringkasan rantai dari `Blockchain::summary`
+//! - `GET /blocks` — seluruh blok
+//! - `GET /block/{index}` — satu blok
+//! - `GET /balance/{address}` — saldo terkonfirmasi
+//! - `POST /transaction` — transaksi JSON, divalidasi lewat `add_transaction`
+
+use crate::{SharedChain, Transaction};
+use serde::Serialize;
+use std::io;
+use std::net::SocketAddr;
+use std::thread::{self, JoinHandle};
+use tiny_http::{Header, Method, Request, Response, Server};
+
+/// Respons yang sudah dirutekan: kode status dan body JSON.
+#[derive(Debug, PartialEq)]
+struct Reply {
+    status: u16,
+    body: String,
+}
+
+impl Reply {
+    fn json(status: u16, value: &impl Serialize) -> Self {
+        let body = serde_json::to_string(value).expect("Nilai respons selalu dapat diserialisasi");
+        Reply { status, body }
+    }
+
+    fn error(status: u16, message: impl Into<String>) -> Self {
+        Self::json(status, &serde_json::json!({ "error": message.into() }))
+    }
+}
+
+/// Menjalankan server di `addr` pada thread terpisah; mengembalikan alamat yang benar-benar
+/// di-bind (berguna untuk port `0`) beserta handle thread-nya.
+pub fn spawn(chain: SharedChain, addr: &str) -> io::Result<(SocketAddr, JoinHandle<()>)> {
+    let server = Server::http(addr).map_err(io::Error::other)?;
+    let local_addr = server
+        .server_addr()
+        .to_ip()
+        .ok_or_else(|| io::Error::other("Server tidak terikat ke alamat IP"))?;
+    let handle = thread::spawn(move || {
+        for request in server.incoming_requests() {
+            handle_request(&chain, request);
+        }
+    });
+    Ok((local_addr, handle))
+}
+
+fn handle_request(chain: &SharedChain, mut request: Request) {
+    let mut body = String::new();
+    let reply = match request.as_reader().read_to_string(&mut body) {
+        Ok(_) => route(chain, request.method(), request.url(), &body),
+        Err(_) => Reply::error(400, "Body bukan UTF-8 yang valid"),
+    };
+    let content_type = Header::from_bytes("Content-Type", "application/json").expect("Header statis selalu valid");
+    let response = Response::from_string(reply.body).with_status_code(reply.status).with_header(content_type);
+    // Klien yang memutus koneksi lebih dulu tidak perlu menghentikan server.
+    let _ = request.respond(response);
+}
+
+fn route(chain: &SharedChain, method: &Method, url: &str, body: &str) -> Reply {
+    let path = url.split('?').next().unwrap_or_default();
+    let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+    match (method, segments.as_slice()) {
+        (Method::Get, ["stats"]) => Reply::json(200, &chain.read().summary()),
+        (Method::Get, ["blocks"]) => Reply::json(200, &chain.read().blocks),
+        (Method::Get, ["block", index]) => match index.parse::<usize>() {
+            Ok(index) => match chain.read().blocks.get(index) {
+                Some(block) => Reply::json(200, block),
+                None => Reply::error(404, format!("Blok #{} tidak ditemukan", index)),
+            },
+            Err(_) => Reply::error(400, "Indeks blok harus berupa angka"),
+        },
+        (Method::Get, ["balance", address]) => {
+            let balance = chain.read().get_balance(address);
+            Reply::json(200, &serde_json::json!({ "address": address, "balance": balance }))
+        }
+        (Method::Post, ["transaction"]) => match serde_json::from_str::<Transaction>(body) {
+            Ok(tx) => {
+                let id = tx.id.clone();
+                match chain.write().add_transaction(tx) {
+                    Ok(()) => Reply::json(200, &serde_json::json!({ "id": id })),
+                    Err(err) => Reply::error(422, format!("{:?}", err)),
+                }
+            }
+            Err(err) => Reply::error(400, format!("Transaksi tidak valid: {}", err)),
+        },
+        _ => Reply::error(404, "Endpoint tidak ditemukan"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Block, Blockchain, ConsensusParams};
+    use std::io::{Read, Write};
+    use std::net::TcpStream;
+
+    fn shared_chain() -> SharedChain {
+        let params = ConsensusParams { initial_difficulty: 1, ..ConsensusParams::default() };
+        SharedChain::new(Blockchain::new("miner".into(), params).unwrap())
+    }
+
+    /// Mengirim request HTTP/1.1 mentah dan mengembalikan body respons.
+    fn http_get(addr: SocketAddr, path: &str) -> String {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        write!(stream, "GET {} HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n", path).unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        response.split_once("\r\n\r\n").unwrap().1.to_string()
+    }
+
+    #[test]
+    fn test_get_genesis_block_over_http() {
+        let chain = shared_chain();
+        let genesis_hash = chain.read().blocks[0].hash.clone();
+        let (addr, _handle) = spawn(chain, "127.0.0.1:0").unwrap();
+
+        let block: Block = serde_json::from_str(&http_get(addr, "/block/0")).unwrap();
+        assert_eq!(block.index, 0);
+        assert_eq!(block.hash, genesis_hash);
+    }
+
+    #[test]
+    fn test_route_balance_and_missing_block() {
+        let chain = shared_chain();
+        let reply = route(&chain, &Method::Get, "/balance/miner", "");
+        assert_eq!(reply, Reply::json(200, &serde_json::json!({ "address": "miner", "balance": 50 })));
+        assert_eq!(route(&chain, &Method::Get, "/block/9", "").status, 404);
+        assert_eq!(route(&chain, &Method::Get, "/block/abc", "").status, 400);
+        assert_eq!(route(&chain, &Method::Get, "/unknown", "").status, 404);
+        assert_eq!(route(&chain, &Method::Get, "/stats", ""), Reply::json(200, &chain.read().summary()));
+    }
+
+    #[test]
+    fn test_route_post_transaction_runs_mempool_validation() {
+        let chain = shared_chain();
+        let unsigned = Transaction::new("miner".into(), "alice".into(), 5, "sig".into());
+        let reply = route(&chain, &Method::Post, "/transaction", &serde_json::to_string(&unsigned).unwrap());
+        assert_eq!(reply.status, 422);
+        assert!(chain.read().pending_transactions.is_empty());
+        assert_eq!(route(&chain, &Method::Post, "/transaction", "{").status, 400);
+    }
+}
@@ -0,0 +1,166 @@
+//! Abstraksi skema signature transaksi.
+//!
+//! `Transaction` menyimpan tag skema di field `scheme`; `Transaction::verify_signature` memilih
+//! `Verifier` yang tag-nya cocok dari `default_verifiers`, atau dari daftar sendiri lewat
+//! `Transaction::verify_signature_with`. Ed25519 adalah skema bawaan. Skema lain (mis. secp256k1
+//! untuk kunci gaya Bitcoin/Ethereum) cukup mengimplementasikan `Signer` dan `Verifier` dengan
+//! format kunci dan signature hex miliknya sendiri.
+
+use crate::{decode_hex, encode_hex};
+use ed25519_dalek::{Signature, SigningKey, VerifyingKey};
+
+/// Tag skema ed25519, dipakai sebagai default untuk transaksi tanpa field `scheme`.
+pub const ED25519: &str = "ed25519";
+
+/// Pemilik kunci privat yang bisa menandatangani pesan.
+pub trait Signer {
+    /// Tag skema yang disimpan di transaksi yang ditandatangani.
+    fn scheme(&self) -> &'static str;
+    /// Public key hex, dipakai sebagai alamat pengirim.
+    fn public_key(&self) -> String;
+    /// Signature hex atas `message`.
+    fn sign(&self, message: &[u8]) -> String;
+}
+
+/// Pemeriksa signature untuk satu skema.
+pub trait Verifier: Send + Sync {
+    fn scheme(&self) -> &'static str;
+    /// `public_key` dan `signature` dalam hex; format yang tidak valid berarti gagal.
+    fn verify(&self, public_key: &str, message: &[u8], signature: &str) -> bool;
+}
+
+impl Signer for SigningKey {
+    fn scheme(&self) -> &'static str {
+        ED25519
+    }
+
+    fn public_key(&self) -> String {
+        encode_hex(self.verifying_key().as_bytes())
+    }
+
+    fn sign(&self, message: &[u8]) -> String {
+        encode_hex(&ed25519_dalek::Signer::sign(self, message).to_bytes())
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Ed25519Verifier;
+
+impl Verifier for Ed25519Verifier {
+    fn scheme(&self) -> &'static str {
+        ED25519
+    }
+
+    fn verify(&self, public_key: &str, message: &[u8], signature: &str) -> bool {
+        let Some(public_key) = decode_hex(public_key).and_then(|bytes| <[u8; 32]>::try_from(bytes).ok()) else {
+            return false;
+        };
+        let Some(signature) = decode_hex(signature).and_then(|bytes| <[u8; 64]>::try_from(bytes).ok()) else {
+            return false;
+        };
+        VerifyingKey::from_bytes(&public_key)
+            .is_ok_and(|key| ed25519_dalek::Verifier::verify(&key, message, &Signature::from_bytes(&signature)).is_ok())
+    }
+}
+
+/// Skema yang dikenali `Transaction::verify_signature`.
+pub fn default_verifiers() -> &'static [&'static dyn Verifier] {
+    &[&Ed25519Verifier]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Transaction;
+
+    /// Skema tiruan: "signature" adalah public key diikuti panjang pesan, cukup untuk menguji dispatch.
+    struct MockScheme(&'static str);
+
+    impl Signer for MockScheme {
+        fn scheme(&self) -> &'static str {
+            "mock"
+        }
+
+        fn public_key(&self) -> String {
+            self.0.to_string()
+        }
+
+        fn sign(&self, message: &[u8]) -> String {
+            format!("{}:{}", self.0, message.len())
+        }
+    }
+
+    impl Verifier for MockScheme {
+        fn scheme(&self) -> &'static str {
+            "mock"
+        }
+
+        fn verify(&self, public_key: &str, message: &[u8], signature: &str) -> bool {
+            signature == format!("{}:{}", public_key, message.len())
+        }
+    }
+
+    fn mock_tx(signer: &MockScheme) -> Transaction {
+        let mut tx = Transaction::new(signer.public_key(), "bob".into(), 5, String::new());
+        tx.sign_with(signer);
+        tx
+    }
+
+    #[test]
+    fn test_verify_dispatches_on_scheme_tag() {
+        let mock = MockScheme("alice");
+        let tx = mock_tx(&mock);
+        assert_eq!(tx.scheme, "mock");
+        assert!(tx.verify_signature_with(&[&Ed25519Verifier, &mock]));
+        assert!(!tx.verify_signature(), "Skema yang tidak terdaftar selalu ditolak");
+
+        let mut forged = tx.clone();
+        forged.from = "mallory".into();
+        assert!(!forged.verify_signature_with(&[&mock]));
+    }
+
+    #[test]
+    fn test_unknown_scheme_is_not_verified_as_ed25519() {
+        let key = SigningKey::from_bytes(&[1; 32]);
+        let mut tx = Transaction::new(key.public_key(), "bob".into(), 5, String::new());
+        tx.sign_with(&key);
+        assert_eq!(tx.scheme, ED25519);
+        assert!(tx.verify_signature());
+
+        tx.scheme = "secp256k1".into();
+        assert!(!tx.verify_signature());
+    }
+
+    #[test]
+    fn test_scheme_is_committed_to_transaction_hash() {
+        let key = SigningKey::from_bytes(&[1; 32]);
+        let mut tx = Transaction::new(key.public_key(), "bob".into(), 5, String::new());
+        tx.sign_with(&key);
+        let legacy = Transaction::with_timestamp(tx.from.clone(), tx.to.clone(), tx.amount, tx.signature.clone(), tx.timestamp);
+        assert_eq!(tx.calculate_hash(), legacy.calculate_hash(), "Transaksi tanpa tag eksplisit di-hash sebagai ed25519");
+
+        let mut relabeled = tx.clone();
+        relabeled.scheme = "mock".into();
+        assert_ne!(relabeled.calculate_hash(), tx.calculate_hash());
+        assert_eq!(relabeled.validate(), Err(crate::TxError::IdMismatch), "Skema tidak bisa diganti tanpa mengubah id");
+
+        relabeled.id = relabeled.calculate_hash();
+        assert_ne!(
+            crate::MerkleTree::new(&[relabeled]).build_tree(),
+            crate::MerkleTree::new(&[tx]).build_tree(),
+            "Merkle root ikut berubah bersama skema"
+        );
+    }
+
+    #[test]
+    fn test_missing_scheme_defaults_to_ed25519() {
+        let key = SigningKey::from_bytes(&[1; 32]);
+        let mut tx = Transaction::new(key.public_key(), "bob".into(), 5, String::new());
+        tx.sign(&key);
+        let mut json = serde_json::to_value(&tx).unwrap();
+        json.as_object_mut().unwrap().remove("scheme");
+        let decoded: Transaction = serde_json::from_value(json).unwrap();
+        assert_eq!(decoded.scheme, ED25519);
+        assert!(decoded.verify_signature());
+    }
+}
@@ -0,0 +1,369 @@
+//! Model UTXO (unspent transaction output) sebagai alternatif saldo berbasis akun.
+//!
+//! Setiap transaksi menghabiskan output lama lewat `TxInput` (outpoint `prev_tx:index`) dan
+//! membuat `TxOutput` baru; sisa nilai dikembalikan ke pengirim sebagai output kembalian.
+//! `UtxoSet` dibangun dengan me-replay transaksi secara berurutan dan menolak outpoint yang
+//! tidak dikenal atau sudah dihabiskan, sehingga double-spend tertolak per output, bukan
+//! lewat cek saldo. Pemilik output (`TxOutput::to`) adalah public key ed25519 hex, dan setiap
+//! input harus membawa signature pemilik output yang dihabiskannya atas `id` transaksi.
+//!
+//! Hadiah blok mengikuti aturan yang sama dengan rantai akun: `apply_block` hanya menerima satu
+//! coinbase per blok, di posisi pertama, dengan tinggi blok yang benar dan nilai paling banyak
+//! hadiah ditambah fee transaksi di blok itu. `apply` menolak coinbase agar koin tidak bisa
+//! dicetak di luar aturan tersebut.
+//!
+//! Cakupan modul ini terbatas: `Blockchain` tidak memakainya. Blok tetap membawa `Transaction`
+//! berbasis akun, dan `Blockchain::get_balance` maupun validasi blok tetap berbasis saldo akun.
+//! `UtxoSet` hanya memvalidasi blok `UtxoTransaction` yang diberikan pemanggil, sampai format
+//! transaksi di dalam blok ikut bermigrasi.
+
+use crate::sha256_hex;
+use crate::signature::{Ed25519Verifier, Signer, Verifier};
+use ed25519_dalek::SigningKey;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+/// Referensi ke output ke-`index` dari transaksi `prev_tx`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TxInput {
+    pub prev_tx: String,
+    pub index: u32,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TxOutput {
+    pub to: String,
+    pub amount: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct UtxoTransaction {
+    pub id: String,
+    pub inputs: Vec<TxInput>,
+    pub outputs: Vec<TxOutput>,
+    /// Signature hex untuk setiap input, sejajar dengan `inputs`. Tidak ikut di-hash ke `id`,
+    /// karena `id` itulah yang ditandatangani.
+    #[serde(default)]
+    pub signatures: Vec<String>,
+    /// Tinggi blok untuk coinbase, agar coinbase dengan penerima dan nilai yang sama tetap unik.
+    #[serde(default)]
+    pub height: u64,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum UtxoError {
+    /// Outpoint tidak pernah dibuat oleh transaksi yang sudah di-replay.
+    UnknownOutput(TxInput),
+    /// Outpoint sudah dihabiskan sebelumnya atau dipakai dua kali di transaksi yang sama.
+    DoubleSpend(TxInput),
+    InsufficientInput { input: u64, output: u64 },
+    DuplicateTransaction(String),
+    AmountOverflow,
+    /// `id` bukan hasil `calculate_hash` atas isi transaksi.
+    IdMismatch(String),
+    /// Signature input tidak ada atau bukan milik pemilik output yang dihabiskan.
+    InvalidSignature(TxInput),
+    /// Coinbase di luar posisi pertama blok, atau diterapkan lewat `apply`.
+    UnexpectedCoinbase(String),
+    /// Blok tanpa coinbase, coinbase dengan tinggi yang salah, atau nilainya melebihi hadiah + fee.
+    InvalidCoinbase { height: u64 },
+}
+
+impl UtxoTransaction {
+    /// Membuat transaksi yang belum ditandatangani; `id` diturunkan dari seluruh input dan output.
+    pub fn new(inputs: Vec<TxInput>, outputs: Vec<TxOutput>) -> Self {
+        Self::with_height(inputs, outputs, 0)
+    }
+
+    /// Transaksi tanpa input yang mencetak koin baru; `height` ikut di-hash sehingga id coinbase unik per blok.
+    pub fn coinbase(to: String, amount: u64, height: u64) -> Self {
+        Self::with_height(Vec::new(), vec![TxOutput { to, amount }], height)
+    }
+
+    fn with_height(inputs: Vec<TxInput>, outputs: Vec<TxOutput>, height: u64) -> Self {
+        let signatures = vec![String::new(); inputs.len()];
+        let mut transaction = Self { id: String::new(), inputs, outputs, signatures, height };
+        transaction.id = transaction.calculate_hash();
+        transaction
+    }
+
+    /// Hash dari input, output, dan tinggi; signature tidak termasuk.
+    pub fn calculate_hash(&self) -> String {
+        let inputs: String = self.inputs.iter().map(|input| format!("{}:{};", input.prev_tx, input.index)).collect();
+        let outputs: String = self.outputs.iter().map(|output| format!("{}:{};", output.to, output.amount)).collect();
+        sha256_hex(&format!("{}|{}|{}", inputs, outputs, self.height))
+    }
+
+    /// Menandatangani input ke-`index` dengan kunci pemilik output yang dihabiskannya.
+    pub fn sign_input(&mut self, index: usize, key: &SigningKey) {
+        self.signatures.resize(self.inputs.len(), String::new());
+        self.signatures[index] = key.sign(self.id.as_bytes());
+    }
+
+    pub fn is_coinbase(&self) -> bool {
+        self.inputs.is_empty()
+    }
+
+    /// Outpoint untuk output ke-`index` transaksi ini, untuk dipakai sebagai input berikutnya.
+    pub fn outpoint(&self, index: u32) -> TxInput {
+        TxInput { prev_tx: self.id.clone(), index }
+    }
+
+    fn total_output(&self) -> Result<u64, UtxoError> {
+        self.outputs
+            .iter()
+            .try_fold(0u64, |total, output| total.checked_add(output.amount))
+            .ok_or(UtxoError::AmountOverflow)
+    }
+}
+
+/// Kumpulan output yang belum dihabiskan, di-key oleh outpoint-nya.
+#[derive(Debug, Default, Clone)]
+pub struct UtxoSet {
+    unspent: HashMap<TxInput, TxOutput>,
+    spent: HashSet<TxInput>,
+}
+
+impl UtxoSet {
+    /// Me-replay blok sesuai urutan di rantai; tinggi blok adalah posisinya dan `reward` memberi
+    /// hadiah untuk tinggi tersebut. Berhenti di blok pertama yang tidak valid.
+    pub fn replay<'a>(blocks: impl IntoIterator<Item = &'a [UtxoTransaction]>, reward: impl Fn(u64) -> u64) -> Result<Self, UtxoError> {
+        let mut set = Self::default();
+        for (height, transactions) in blocks.into_iter().enumerate() {
+            set.apply_block(height as u64, transactions, reward(height as u64))?;
+        }
+        Ok(set)
+    }
+
+    /// Menerapkan seluruh transaksi blok di `height`: coinbase di posisi pertama, sisanya transaksi
+    /// biasa. Output coinbase baru ditambahkan setelah transaksi lain, sehingga tidak bisa dihabiskan
+    /// di blok yang sama. Jika gagal, set tidak berubah.
+    pub fn apply_block(&mut self, height: u64, transactions: &[UtxoTransaction], reward: u64) -> Result<(), UtxoError> {
+        let (coinbase, rest) = transactions.split_first().ok_or(UtxoError::InvalidCoinbase { height })?;
+        if !coinbase.is_coinbase() || coinbase.height != height {
+            return Err(UtxoError::InvalidCoinbase { height });
+        }
+        self.check_new(coinbase)?;
+
+        let mut next = self.clone();
+        let mut fees = 0u64;
+        for tx in rest {
+            fees = fees.checked_add(next.apply_spend(tx)?).ok_or(UtxoError::AmountOverflow)?;
+        }
+        let limit = reward.checked_add(fees).ok_or(UtxoError::AmountOverflow)?;
+        if coinbase.total_output()? > limit {
+            return Err(UtxoError::InvalidCoinbase { height });
+        }
+        next.insert_outputs(coinbase);
+        *self = next;
+        Ok(())
+    }
+
+    /// Memvalidasi lalu menerapkan satu transaksi non-coinbase. Jika gagal, set tidak berubah.
+    pub fn apply(&mut self, tx: &UtxoTransaction) -> Result<(), UtxoError> {
+        self.apply_spend(tx).map(|_| ())
+    }
+
+    /// Seperti `apply`, tetapi mengembalikan fee transaksi (input dikurangi output).
+    fn apply_spend(&mut self, tx: &UtxoTransaction) -> Result<u64, UtxoError> {
+        if tx.is_coinbase() {
+            return Err(UtxoError::UnexpectedCoinbase(tx.id.clone()));
+        }
+        self.check_new(tx)?;
+
+        let output_total = tx.total_output()?;
+        let mut seen = HashSet::new();
+        let mut input_total = 0u64;
+        for (position, input) in tx.inputs.iter().enumerate() {
+            if self.spent.contains(input) || !seen.insert(input) {
+                return Err(UtxoError::DoubleSpend(input.clone()));
+            }
+            let output = self.unspent.get(input).ok_or_else(|| UtxoError::UnknownOutput(input.clone()))?;
+            let signature = tx.signatures.get(position).map_or("", String::as_str);
+            if !Ed25519Verifier.verify(&output.to, tx.id.as_bytes(), signature) {
+                return Err(UtxoError::InvalidSignature(input.clone()));
+            }
+            input_total = input_total.checked_add(output.amount).ok_or(UtxoError::AmountOverflow)?;
+        }
+        if input_total < output_total {
+            return Err(UtxoError::InsufficientInput { input: input_total, output: output_total });
+        }
+
+        for input in &tx.inputs {
+            self.unspent.remove(input);
+            self.spent.insert(input.clone());
+        }
+        self.insert_outputs(tx);
+        Ok(input_total - output_total)
+    }
+
+    /// `id` harus cocok dengan isi transaksi dan output-nya belum pernah dibuat.
+    fn check_new(&self, tx: &UtxoTransaction) -> Result<(), UtxoError> {
+        if tx.id != tx.calculate_hash() {
+            return Err(UtxoError::IdMismatch(tx.id.clone()));
+        }
+        if (0..tx.outputs.len() as u32).any(|index| {
+            let outpoint = tx.outpoint(index);
+            self.unspent.contains_key(&outpoint) || self.spent.contains(&outpoint)
+        }) {
+            return Err(UtxoError::DuplicateTransaction(tx.id.clone()));
+        }
+        Ok(())
+    }
+
+    fn insert_outputs(&mut self, tx: &UtxoTransaction) {
+        for (index, output) in tx.outputs.iter().enumerate() {
+            self.unspent.insert(tx.outpoint(index as u32), output.clone());
+        }
+    }
+
+    /// Jumlah seluruh output yang belum dihabiskan milik `owner`.
+    pub fn get_balance(&self, owner: &str) -> u64 {
+        self.unspent.values().filter(|output| output.to == owner).map(|output| output.amount).sum()
+    }
+
+    /// Outpoint yang bisa dihabiskan `owner`, untuk menyusun input transaksi baru.
+    pub fn unspent_for(&self, owner: &str) -> Vec<(TxInput, u64)> {
+        self.unspent
+            .iter()
+            .filter(|(_, output)| output.to == owner)
+            .map(|(outpoint, output)| (outpoint.clone(), output.amount))
+            .collect()
+    }
+
+    pub fn is_unspent(&self, outpoint: &TxInput) -> bool {
+        self.unspent.contains_key(outpoint)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn output(to: &str, amount: u64) -> TxOutput {
+        TxOutput { to: to.into(), amount }
+    }
+
+    fn key(seed: u8) -> SigningKey {
+        SigningKey::from_bytes(&[seed; 32])
+    }
+
+    const REWARD: u64 = 50;
+
+    /// Blok di `height`: coinbase `REWARD` untuk miner diikuti `transactions`.
+    fn block(height: u64, transactions: &[&UtxoTransaction]) -> Vec<UtxoTransaction> {
+        let coinbase = UtxoTransaction::coinbase("miner".into(), REWARD, height);
+        std::iter::once(coinbase).chain(transactions.iter().map(|tx| (*tx).clone())).collect()
+    }
+
+    fn replay(blocks: &[Vec<UtxoTransaction>]) -> Result<UtxoSet, UtxoError> {
+        UtxoSet::replay(blocks.iter().map(Vec::as_slice), |_| REWARD)
+    }
+
+    /// Transaksi yang setiap inputnya ditandatangani `owner`.
+    fn signed(owner: &SigningKey, inputs: Vec<TxInput>, outputs: Vec<TxOutput>) -> UtxoTransaction {
+        let mut tx = UtxoTransaction::new(inputs, outputs);
+        for index in 0..tx.inputs.len() {
+            tx.sign_input(index, owner);
+        }
+        tx
+    }
+
+    #[test]
+    fn test_spend_with_change_output() {
+        let alice = key(1);
+        let coinbase = UtxoTransaction::coinbase(alice.public_key(), 50, 0);
+        let spend = signed(&alice, vec![coinbase.outpoint(0)], vec![output("bob", 30), output(&alice.public_key(), 20)]);
+        let set = replay(&[vec![coinbase.clone()], block(1, &[&spend])]).unwrap();
+
+        assert_eq!(set.get_balance(&alice.public_key()), 20, "Kembalian kembali ke pengirim");
+        assert_eq!(set.get_balance("bob"), 30);
+        assert!(!set.is_unspent(&coinbase.outpoint(0)));
+        assert_eq!(set.unspent_for(&alice.public_key()), vec![(spend.outpoint(1), 20)]);
+    }
+
+    #[test]
+    fn test_spending_already_spent_output_is_rejected() {
+        let (alice, bob) = (key(1), key(2));
+        let coinbase = UtxoTransaction::coinbase(alice.public_key(), 50, 0);
+        let first = signed(&alice, vec![coinbase.outpoint(0)], vec![output(&bob.public_key(), 50)]);
+        let second = signed(&alice, vec![coinbase.outpoint(0)], vec![output("carol", 50)]);
+        let mut set = replay(&[vec![coinbase.clone()], block(1, &[&first])]).unwrap();
+
+        assert_eq!(set.apply(&second), Err(UtxoError::DoubleSpend(coinbase.outpoint(0))));
+        assert_eq!(set.get_balance("carol"), 0);
+        assert_eq!(set.get_balance(&bob.public_key()), 50);
+
+        let twice = signed(&bob, vec![first.outpoint(0), first.outpoint(0)], vec![output("carol", 100)]);
+        assert_eq!(set.apply(&twice), Err(UtxoError::DoubleSpend(first.outpoint(0))), "Outpoint yang sama dua kali dalam satu transaksi");
+    }
+
+    #[test]
+    fn test_rejects_unknown_output_and_overspend() {
+        let alice = key(1);
+        let coinbase = UtxoTransaction::coinbase(alice.public_key(), 50, 0);
+        let mut set = replay(&[vec![coinbase.clone()]]).unwrap();
+
+        let unknown = TxInput { prev_tx: "tidak-ada".into(), index: 0 };
+        let forged = signed(&alice, vec![unknown.clone()], vec![output("bob", 1)]);
+        assert_eq!(set.apply(&forged), Err(UtxoError::UnknownOutput(unknown)));
+
+        let overspend = signed(&alice, vec![coinbase.outpoint(0)], vec![output("bob", 51)]);
+        assert_eq!(set.apply(&overspend), Err(UtxoError::InsufficientInput { input: 50, output: 51 }));
+        assert!(set.is_unspent(&coinbase.outpoint(0)), "Transaksi yang ditolak tidak mengubah set");
+        assert_eq!(set.apply_block(0, std::slice::from_ref(&coinbase), REWARD), Err(UtxoError::DuplicateTransaction(coinbase.id.clone())));
+    }
+
+    #[test]
+    fn test_spend_requires_owner_signature() {
+        let (alice, mallory) = (key(1), key(2));
+        let coinbase = UtxoTransaction::coinbase(alice.public_key(), 50, 0);
+        let mut set = replay(&[vec![coinbase.clone()]]).unwrap();
+
+        let unsigned = UtxoTransaction::new(vec![coinbase.outpoint(0)], vec![output(&mallory.public_key(), 50)]);
+        assert_eq!(set.apply(&unsigned), Err(UtxoError::InvalidSignature(coinbase.outpoint(0))));
+        let stolen = signed(&mallory, vec![coinbase.outpoint(0)], vec![output(&mallory.public_key(), 50)]);
+        assert_eq!(set.apply(&stolen), Err(UtxoError::InvalidSignature(coinbase.outpoint(0))), "Signature bukan milik pemilik output");
+
+        let mut redirected = signed(&alice, vec![coinbase.outpoint(0)], vec![output("bob", 50)]);
+        redirected.outputs[0].to = mallory.public_key();
+        assert_eq!(set.apply(&redirected), Err(UtxoError::IdMismatch(redirected.id.clone())));
+        redirected.id = redirected.calculate_hash();
+        assert_eq!(set.apply(&redirected), Err(UtxoError::InvalidSignature(coinbase.outpoint(0))), "Signature lama tidak berlaku untuk output baru");
+        assert!(set.is_unspent(&coinbase.outpoint(0)));
+    }
+
+    #[test]
+    fn test_coinbase_id_is_hash_of_contents() {
+        let first = UtxoTransaction::coinbase("miner".into(), 50, 0);
+        let second = UtxoTransaction::coinbase("miner".into(), 50, 1);
+        assert_eq!(first.id, first.calculate_hash());
+        assert_ne!(first.id, second.id, "Tinggi blok membuat coinbase unik");
+        assert!(replay(&[vec![first], vec![second]]).is_ok());
+    }
+
+    #[test]
+    fn test_apply_block_enforces_coinbase_rules() {
+        let alice = key(1);
+        let coinbase = UtxoTransaction::coinbase(alice.public_key(), 50, 0);
+        let mut set = replay(&[vec![coinbase.clone()]]).unwrap();
+        let spend = signed(&alice, vec![coinbase.outpoint(0)], vec![output("bob", 45)]);
+
+        let greedy = UtxoTransaction::coinbase("miner".into(), REWARD + 6, 1);
+        assert_eq!(set.apply_block(1, &[greedy, spend.clone()], REWARD), Err(UtxoError::InvalidCoinbase { height: 1 }), "Fee hanya 5");
+        assert_eq!(set.get_balance("bob"), 0, "Blok yang ditolak tidak mengubah set");
+
+        let wrong_height = UtxoTransaction::coinbase("miner".into(), REWARD, 7);
+        assert_eq!(set.apply_block(1, &[wrong_height], REWARD), Err(UtxoError::InvalidCoinbase { height: 1 }));
+        assert_eq!(set.apply_block(1, std::slice::from_ref(&spend), REWARD), Err(UtxoError::InvalidCoinbase { height: 1 }), "Blok tanpa coinbase");
+
+        let extra = UtxoTransaction::coinbase("mallory".into(), 1, 1);
+        assert_eq!(set.apply_block(1, &block(1, &[&extra]), REWARD), Err(UtxoError::UnexpectedCoinbase(extra.id.clone())));
+        assert_eq!(set.apply(&extra), Err(UtxoError::UnexpectedCoinbase(extra.id.clone())));
+
+        let fair = UtxoTransaction::coinbase("miner".into(), REWARD + 5, 1);
+        set.apply_block(1, &[fair, spend], REWARD).unwrap();
+        assert_eq!(set.get_balance("miner"), REWARD + 5);
+        assert_eq!(set.get_balance("bob"), 45);
+    }
+}